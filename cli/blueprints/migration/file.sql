@@ -0,0 +1,7 @@
+-- migrate:up
+-- The statements applying the "{{name}}" migration. Remember to update db/schema.sql as well.
+
+
+-- migrate:down
+-- The statements reverting the "{{name}}" migration.
+
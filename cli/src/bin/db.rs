@@ -1,21 +1,48 @@
 use anyhow::{anyhow, Context};
-use clap::{Parser, Subcommand};
-use csv_async::{AsyncReaderBuilder, StringRecord, Trim};
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use csv_async::AsyncWriterBuilder;
 use futures::stream::TryStreamExt;
 use guppy::{Version, VersionReq};
+use reqwest::header::{ETAG, LAST_MODIFIED};
 use reqwest::Client;
-use restations_cli::util::ui::UI;
+use restations_cli::importers::trainline::CARRIER_ID_COLUMNS;
+use restations_cli::importers::{importer, Importer, ReadError, Rejection, StationRecord};
+use restations_cli::lint::{lint, Check, Finding, Severity};
+use restations_cli::merge::{self, merge, MergedStation, SourceStations};
+use restations_cli::overrides::{self, OverridesReport};
+use restations_cli::snapshots::{self, Snapshot};
+use restations_cli::util::ui::{Progress, UI};
 use restations_config::DatabaseConfig;
-use restations_config::{load_config, parse_env, Config, Environment};
-use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use restations_config::{
+    load_config, parse_env, parse_source_format, Config, Environment, SourceConfig, SourceFormat,
+    SyncConfig,
+};
+use restations_db::entities::alternative_ids::{self, AlternativeId};
+use restations_db::entities::dataset_metadata::DatasetMetadata;
+use restations_db::entities::stations::{self, Station};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqliteSynchronous};
 use sqlx::{ConnectOptions, Connection};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::process::{ExitCode, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tempfile::NamedTempFile;
 use tokio::{
-    fs::{read_to_string, remove_file, File},
-    io::{stdin, AsyncBufReadExt},
+    fs::{metadata, read_dir, read_to_string, remove_file, set_permissions, File},
+    io::{
+        stdin, stdout, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter,
+    },
 };
 use tokio_stream::StreamExt;
+use tokio_util::io::InspectReader;
 use url::Url;
 
 #[tokio::main]
@@ -25,6 +52,7 @@ async fn main() -> ExitCode {
 
     let args = Cli::parse();
     let mut ui = UI::new(&mut stdout, &mut stderr, !args.no_color, !args.quiet);
+    ui.set_interactive(std::io::stdout().is_terminal() && !args.quiet);
 
     match cli(&mut ui, args).await {
         Ok(_) => ExitCode::SUCCESS,
@@ -58,12 +86,130 @@ enum Commands {
     Drop,
     #[command(about = "Create the database")]
     Create,
+    #[command(about = "Apply all pending migrations from db/migrations")]
+    Migrate,
+    #[command(about = "Revert the most recently applied migrations or restore a snapshot")]
+    Rollback(RollbackArgs),
     #[command(about = "Synchronize the database with the source data")]
-    Sync,
+    Sync(SyncArgs),
+    #[command(
+        about = "Export the stations to a file or stdout",
+        long_about = "Export the stations with their alternative IDs to a file or stdout. The JSON, \
+            NDJSON and GeoJSON exports include the source of each station's name and coordinates; \
+            the CSV export leaves it out as the synchronization records it anew."
+    )]
+    Export(ExportArgs),
+    #[command(about = "Check the stations for data quality problems")]
+    Lint(LintArgs),
+    #[command(about = "List the snapshots of the synchronized database")]
+    Snapshots(SnapshotsArgs),
     #[command(about = "Generate query metadata to support offline compile-time verification")]
     Prepare,
 }
 
+#[derive(Args)]
+struct RollbackArgs {
+    #[arg(
+        help = "The ID of the snapshot to restore instead of reverting migrations, see `cargo db snapshots list`."
+    )]
+    snapshot: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "1",
+        conflicts_with = "snapshot",
+        help = "The number of migrations to revert."
+    )]
+    steps: NonZeroUsize,
+}
+
+#[derive(Args)]
+struct SnapshotsArgs {
+    #[command(subcommand)]
+    command: SnapshotsCommands,
+}
+
+#[derive(Subcommand)]
+enum SnapshotsCommands {
+    #[command(about = "List the snapshots, newest first")]
+    List,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[arg(long, value_enum, help = "The format to export the stations in.")]
+    format: ExportFormat,
+
+    #[arg(short, long, help = "Write the export to this file instead of stdout.")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct LintArgs {
+    #[arg(
+        long,
+        help = "Print the findings as JSON instead of a human-readable report."
+    )]
+    json: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "error",
+        help = "Fail if there are findings of this severity or higher."
+    )]
+    fail_on: Severity,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// The `;`-separated format of the source data, which `cargo db sync --from` can read
+    Csv,
+    /// A JSON array of stations
+    Json,
+    /// One JSON object per station and line
+    Ndjson,
+    /// A GeoJSON FeatureCollection with a Point feature per station
+    Geojson,
+}
+
+#[derive(Args)]
+struct SyncArgs {
+    #[arg(
+        long,
+        default_value = "1000",
+        help = "The number of stations to import per transaction."
+    )]
+    batch_size: NonZeroUsize,
+
+    #[arg(
+        long,
+        help = "Read the data of the configured source with the highest priority from a local file instead; the other configured sources are read as usual. Pass - to read from stdin."
+    )]
+    from: Option<String>,
+
+    #[arg(
+        long,
+        help = "Skip invalid rows instead of aborting the synchronization."
+    )]
+    skip_invalid: bool,
+
+    #[arg(
+        long,
+        requires = "skip_invalid",
+        help = "Write the skipped rows to this file as CSV."
+    )]
+    rejects_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "from",
+        value_parser = parse_source_format,
+        help = "The format of the file passed via --from (trainline, gtfs, netex); defaults to the format of the configured source with the highest priority."
+    )]
+    format: Option<SourceFormat>,
+}
+
 #[allow(missing_docs)]
 async fn cli(ui: &mut UI<'_>, cli: Cli) -> Result<(), anyhow::Error> {
     let config: Result<Config, anyhow::Error> = load_config(&cli.env);
@@ -86,17 +232,165 @@ async fn cli(ui: &mut UI<'_>, cli: Cli) -> Result<(), anyhow::Error> {
                     ui.success(&format!("Created database {} successfully.", db_name));
                     Ok(())
                 }
-                Commands::Sync => {
+                Commands::Migrate => {
+                    ui.info(&format!("Migrating {} database…", &cli.env));
+                    let mut conn = get_db_client(&config.database).await;
+                    let migrations = load_migrations(&migrations_dir()?).await?;
+                    let applied = migrate(&mut conn, &migrations)
+                        .await
+                        .context("Could not migrate database!")?;
+                    if applied.is_empty() {
+                        ui.success("Database is up to date.");
+                    } else {
+                        // migrations may change the search index, which only Rust code fills
+                        stations::rebuild_search_index(&mut conn)
+                            .await
+                            .context("Could not rebuild search index!")?;
+                        ui.success(&format!("Applied {} migrations.", applied.len()));
+                        ui.indent();
+                        for migration in applied {
+                            ui.log(&migration.to_string());
+                        }
+                        ui.outdent();
+                    }
+                    Ok(())
+                }
+                Commands::Rollback(RollbackArgs {
+                    snapshot: Some(snapshot),
+                    ..
+                }) => {
+                    ui.info(&format!(
+                        "Restoring snapshot {} of {} database…",
+                        snapshot, &cli.env
+                    ));
+                    let snapshot = snapshots::find(&config.snapshots.dir, &snapshot).await?;
+                    restore_snapshot(&config.database, &snapshot)
+                        .await
+                        .context("Could not restore snapshot!")?;
+                    ui.success(&format!("Restored snapshot {}.", snapshot));
+                    Ok(())
+                }
+                Commands::Rollback(args) => {
+                    ui.info(&format!("Rolling back {} database…", &cli.env));
+                    let mut conn = get_db_client(&config.database).await;
+                    let migrations = load_migrations(&migrations_dir()?).await?;
+                    let reverted = rollback(&mut conn, &migrations, args.steps.get())
+                        .await
+                        .context("Could not roll back database!")?;
+                    if reverted.is_empty() {
+                        ui.success("No migrations to revert.");
+                    } else {
+                        ui.success(&format!("Reverted {} migrations.", reverted.len()));
+                        ui.indent();
+                        for migration in reverted {
+                            ui.log(&migration.to_string());
+                        }
+                        ui.outdent();
+                    }
+                    Ok(())
+                }
+                Commands::Sync(args) => {
                     ui.info(&format!("Synchronizing {} database…", &cli.env));
                     ui.indent();
-                    let stations = sync(&config)
+                    let report = sync(ui, &config, &args)
                         .await
                         .context("Could not synchronize database!");
                     ui.outdent();
-                    let stations = stations?;
-                    ui.success(&format!("{} stations synchronized.", stations));
+                    let report = report?;
+                    ui.success(&format!("{} stations synchronized.", report.total()));
+                    ui.indent();
+                    ui.log(&format!("{} inserted", report.inserted));
+                    ui.log(&format!("{} updated", report.updated));
+                    ui.log(&format!("{} deleted", report.deleted));
+                    ui.log(&format!("{} unchanged", report.unchanged));
+                    if !report.rejected.is_empty() {
+                        ui.log(&format!("{} skipped", report.rejected.len()));
+                    }
+                    ui.outdent();
+                    Ok(())
+                }
+                Commands::Export(args) => {
+                    let mut conn = get_db_client(&config.database).await;
+                    match &args.output {
+                        Some(path) => {
+                            ui.info(&format!("Exporting {} database…", &cli.env));
+                            let file = File::create(path)
+                                .await
+                                .context(format!("Failed to create {}!", path.display()))?;
+                            let count = export(&mut conn, args.format, file)
+                                .await
+                                .context("Could not export database!")?;
+                            ui.success(&format!(
+                                "Exported {} stations to {}.",
+                                count,
+                                path.display()
+                            ));
+                        }
+                        // Messages would end up in the exported data so there are none.
+                        None => {
+                            export(&mut conn, args.format, stdout())
+                                .await
+                                .context("Could not export database!")?;
+                        }
+                    }
+                    Ok(())
+                }
+                Commands::Lint(args) => {
+                    let mut conn = get_db_client(&config.database).await;
+                    let stations = stations::load_all(&mut conn)
+                        .await
+                        .context("Could not load stations!")?;
+                    let findings = lint(&stations);
+
+                    if args.json {
+                        println!("{}", serde_json::to_string_pretty(&findings)?);
+                    } else {
+                        ui.info(&format!(
+                            "Linting {} stations in {} database…",
+                            stations.len(),
+                            &cli.env
+                        ));
+                        report_findings(ui, &findings);
+                    }
+
+                    let failures = findings
+                        .iter()
+                        .filter(|finding| finding.severity >= args.fail_on)
+                        .count();
+                    if failures > 0 {
+                        return Err(anyhow!(
+                            "Found {} problems of severity {} or higher!",
+                            failures,
+                            args.fail_on
+                        ));
+                    }
                     Ok(())
                 }
+                Commands::Snapshots(args) => match args.command {
+                    SnapshotsCommands::List => {
+                        let snapshots = snapshots::list(&config.snapshots.dir).await?;
+                        if snapshots.is_empty() {
+                            ui.info(&format!(
+                                "No snapshots in {}.",
+                                config.snapshots.dir.display()
+                            ));
+                            return Ok(());
+                        }
+                        let mut conn = get_db_client(&config.database).await;
+                        let live_metadata = snapshots::load_metadata(&mut conn).await?;
+                        ui.info(&format!(
+                            "{} snapshots in {}:",
+                            snapshots.len(),
+                            config.snapshots.dir.display()
+                        ));
+                        ui.indent();
+                        for snapshot in snapshots.iter().rev() {
+                            report_snapshot(ui, snapshot, &live_metadata).await?;
+                        }
+                        ui.outdent();
+                        Ok(())
+                    }
+                },
                 Commands::Prepare => {
                     if let Err(e) = ensure_sqlx_cli_installed(ui).await {
                         return Err(e.context("Error ensuring sqlx-cli is installed!"));
@@ -153,408 +447,2746 @@ async fn create(config: &DatabaseConfig) -> Result<String, anyhow::Error> {
 
     let mut connection = get_db_client(config).await;
 
-    let db_package_root = db_package_root().context("Failed to get db package root!")?;
-    let schema_file = PathBuf::from_iter([db_package_root, "schema.sql".into()]);
-    let statements = read_to_string(schema_file)
-        .await
-        .expect("Could not read schema – make sure db/schema.sql exists!");
+    let statements = read_schema().await?;
 
     sqlx::query(statements.as_str())
         .execute(&mut connection)
         .await
         .context("Failed to create schema!")?;
 
+    // The schema already reflects all existing migrations so they must not be applied again.
+    let migrations = load_migrations(&migrations_dir()?).await?;
+    mark_migrations_applied(&mut connection, &migrations).await?;
+
     Ok(db_file_name.to_string_lossy().to_string())
 }
 
-struct StationRecord {
-    pub id: i64,
-    pub name: String,
-    pub uic: String,
-    pub latitude: Option<f64>,
-    pub longitude: Option<f64>,
-    pub country: Option<String>,
-    pub info_de: Option<String>,
-    pub info_en: Option<String>,
-    pub info_es: Option<String>,
-    pub info_fr: Option<String>,
-    pub info_it: Option<String>,
-    pub info_nb: Option<String>,
-    pub info_nl: Option<String>,
-    pub info_cs: Option<String>,
-    pub info_da: Option<String>,
-    pub info_hu: Option<String>,
-    pub info_ja: Option<String>,
-    pub info_ko: Option<String>,
-    pub info_pl: Option<String>,
-    pub info_pt: Option<String>,
-    pub info_ru: Option<String>,
-    pub info_sv: Option<String>,
-    pub info_tr: Option<String>,
-    pub info_zh: Option<String>,
-}
-
-async fn sync(config: &Config) -> Result<i32, anyhow::Error> {
-    let client = Client::new();
-    let response = client.get(config.source_data_file.clone()).send().await?;
-    let stream = response
-        .bytes_stream()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
-    let reader = tokio_util::io::StreamReader::new(stream);
-
-    let mut rdr = AsyncReaderBuilder::new()
-        .trim(Trim::All)
-        .delimiter(b';')
-        .create_reader(reader);
-    let mut records = rdr.records();
+async fn read_schema() -> Result<String, anyhow::Error> {
+    let db_package_root = db_package_root().context("Failed to get db package root!")?;
+    let schema_file = PathBuf::from_iter([db_package_root, "schema.sql".into()]);
+    let statements = read_to_string(schema_file)
+        .await
+        .expect("Could not read schema – make sure db/schema.sql exists!");
+    Ok(statements)
+}
 
-    let mut conn = get_db_client(&config.database).await;
+fn migrations_dir() -> Result<PathBuf, anyhow::Error> {
+    Ok(db_package_root()
+        .context("Failed to get db package root!")?
+        .join("migrations"))
+}
 
-    let mut i = 0;
-    while let Some(record) = records.next().await {
-        let record = record.context("Failed to read record from CSV file!")?;
-        let station = prepare_station(record, i)?;
+/// The line separating the statements applying a migration from those reverting it.
+const MIGRATION_DOWN_MARKER: &str = "-- migrate:down";
 
-        sqlx::query(
-            r#"
-            INSERT INTO
-                stations
-            (
-                id,
-                name,
-                uic,
-                latitude,
-                longitude,
-                country,
-                info_de,
-                info_en,
-                info_es,
-                info_fr,
-                info_it,
-                info_nb,
-                info_nl,
-                info_cs,
-                info_da,
-                info_hu,
-                info_ja,
-                info_ko,
-                info_pl,
-                info_pt,
-                info_ru,
-                info_sv,
-                info_tr,
-                info_zh
-            )
-            VALUES (
-                ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+/// A schema migration from `db/migrations`, named `<version>__<name>.sql`.
+#[derive(Debug, PartialEq)]
+struct Migration {
+    /// The timestamp the migration was generated at, which determines the order of migrations
+    version: i64,
+    name: String,
+    /// The statements applying the migration
+    up: String,
+    /// The statements reverting the migration, following the `-- migrate:down` line
+    down: String,
+}
+
+impl Migration {
+    fn parse(file_name: &str, contents: &str) -> Result<Self, anyhow::Error> {
+        let invalid = || {
+            anyhow!(
+                "Invalid migration file name {}, expected <version>__<name>.sql!",
+                file_name
             )
-            "#,
-        )
-        .bind(station.id)
-        .bind(station.name)
-        .bind(station.uic)
-        .bind(station.latitude)
-        .bind(station.longitude)
-        .bind(station.country)
-        .bind(station.info_de)
-        .bind(station.info_en)
-        .bind(station.info_es)
-        .bind(station.info_fr)
-        .bind(station.info_it)
-        .bind(station.info_nb)
-        .bind(station.info_nl)
-        .bind(station.info_cs)
-        .bind(station.info_da)
-        .bind(station.info_hu)
-        .bind(station.info_ja)
-        .bind(station.info_ko)
-        .bind(station.info_pl)
-        .bind(station.info_pt)
-        .bind(station.info_ru)
-        .bind(station.info_sv)
-        .bind(station.info_tr)
-        .bind(station.info_zh)
-        .execute(&mut conn)
-        .await?;
-        i += 1;
+        };
+        let (version, name) = file_name
+            .strip_suffix(".sql")
+            .and_then(|stem| stem.split_once("__"))
+            .ok_or_else(invalid)?;
+        let version = version.parse().map_err(|_| invalid())?;
+
+        let mut lines = contents.lines();
+        let up: Vec<&str> = lines
+            .by_ref()
+            .take_while(|line| line.trim() != MIGRATION_DOWN_MARKER)
+            .collect();
+        let down: Vec<&str> = lines.collect();
+
+        Ok(Migration {
+            version,
+            name: name.to_string(),
+            up: up.join("\n"),
+            down: down.join("\n"),
+        })
     }
+}
 
-    Ok(i)
+impl Display for Migration {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}__{}", self.version, self.name)
+    }
 }
 
-fn get_db_config(config: &DatabaseConfig) -> SqliteConnectOptions {
-    let db_url = Url::parse(&config.url).expect("Invalid DATABASE_URL!");
-    ConnectOptions::from_url(&db_url).expect("Invalid DATABASE_URL!")
+/// Checks whether the passed SQL contains anything but comments and whitespace.
+fn has_statements(sql: &str) -> bool {
+    sql.lines().any(|line| {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with("--")
+    })
 }
 
-async fn get_db_client(config: &DatabaseConfig) -> SqliteConnection {
-    let db_config = get_db_config(config);
-    let connection = SqliteConnection::connect_with(&db_config).await.unwrap();
+/// Loads all migrations from the passed directory, ordered by version.
+async fn load_migrations(dir: &Path) -> Result<Vec<Migration>, anyhow::Error> {
+    let mut migrations = Vec::new();
+    let mut entries = match read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(migrations),
+        Err(e) => return Err(anyhow!(e).context(format!("Failed to read {}!", dir.display()))),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.ends_with(".sql") {
+            continue;
+        }
+        let contents = read_to_string(entry.path())
+            .await
+            .context(format!("Failed to read migration {}!", file_name))?;
+        migrations.push(Migration::parse(&file_name, &contents)?);
+    }
+
+    migrations.sort_by_key(|migration| migration.version);
+    if let Some(pair) = migrations
+        .windows(2)
+        .find(|pair| pair[0].version == pair[1].version)
+    {
+        return Err(anyhow!(
+            "Migrations {} and {} have the same version!",
+            pair[0],
+            pair[1]
+        ));
+    }
 
-    connection
+    Ok(migrations)
 }
 
-fn get_cargo_path() -> Result<String, anyhow::Error> {
-    std::env::var("CARGO")
-        .map_err(|_| anyhow!("Please invoke me using Cargo, e.g.: `cargo db <ARGS>`"))
+/// Creates the table tracking applied migrations for databases created before it was added to
+/// `db/schema.sql`. The definition must be kept in sync with the schema.
+async fn ensure_migrations_table(conn: &mut SqliteConnection) -> Result<(), anyhow::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(conn)
+    .await
+    .context("Failed to create schema_migrations table!")?;
+    Ok(())
 }
 
-fn prepare_station(record: StringRecord, i: i32) -> Result<StationRecord, anyhow::Error> {
-    let id = record.get(0);
-    let name = record.get(1);
-    let uic = record.get(3);
-    let lat = record.get(5);
-    let lon = record.get(6);
-    let country = record.get(8);
-    let info_de = record.get(54);
-    let info_en = record.get(55);
-    let info_es = record.get(56);
-    let info_fr = record.get(57);
-    let info_it = record.get(58);
-    let info_nb = record.get(59);
-    let info_nl = record.get(60);
-    let info_cs = record.get(61);
-    let info_da = record.get(62);
-    let info_hu = record.get(63);
-    let info_ja = record.get(64);
-    let info_ko = record.get(65);
-    let info_pl = record.get(66);
-    let info_pt = record.get(67);
-    let info_ru = record.get(68);
-    let info_sv = record.get(69);
-    let info_tr = record.get(70);
-    let info_zh = record.get(71);
-
-    match (
-        id, name, uic, lat, lon, country, info_de, info_en, info_es, info_fr, info_it, info_nb,
-        info_nl, info_cs, info_da, info_hu, info_ja, info_ko, info_pl, info_pt, info_ru, info_sv,
-        info_tr, info_zh,
-    ) {
-        (
-            Some(id),
-            Some(name),
-            Some(uic),
-            Some(lat),
-            Some(lon),
-            Some(country),
-            Some(info_de),
-            Some(info_en),
-            Some(info_es),
-            Some(info_fr),
-            Some(info_it),
-            Some(info_nb),
-            Some(info_nl),
-            Some(info_cs),
-            Some(info_da),
-            Some(info_hu),
-            Some(info_ja),
-            Some(info_ko),
-            Some(info_pl),
-            Some(info_pt),
-            Some(info_ru),
-            Some(info_sv),
-            Some(info_tr),
-            Some(info_zh),
-        ) => {
-            let id = id
-                .parse::<i64>()
-                .context(format!("Failed to parse ID to i64: {}", id))?;
-            let lat = if lat.trim().is_empty() {
-                None
-            } else {
-                Some(
-                    lat.parse::<f64>()
-                        .context(format!("Failed to parse latitude to f64: {}", lat))?,
-                )
-            };
-            let lon = if lon.trim().is_empty() {
-                None
-            } else {
-                Some(
-                    lon.parse::<f64>()
-                        .context(format!("Failed to parse longitude to f64: {}", lon))?,
-                )
-            };
+/// Loads the versions of all applied migrations, most recent first.
+async fn applied_migrations(conn: &mut SqliteConnection) -> Result<Vec<i64>, anyhow::Error> {
+    ensure_migrations_table(&mut *conn).await?;
+    let versions =
+        sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version DESC")
+            .fetch_all(conn)
+            .await
+            .context("Failed to load applied migrations!")?;
+    Ok(versions)
+}
 
-            Ok(StationRecord {
-                id,
-                name: name.to_string(),
-                uic: uic.to_string(),
-                latitude: lat,
-                longitude: lon,
-                country: prepare_csv_string(country),
-                info_de: prepare_csv_string(info_de),
-                info_en: prepare_csv_string(info_en),
-                info_es: prepare_csv_string(info_es),
-                info_fr: prepare_csv_string(info_fr),
-                info_it: prepare_csv_string(info_it),
-                info_nb: prepare_csv_string(info_nb),
-                info_nl: prepare_csv_string(info_nl),
-                info_cs: prepare_csv_string(info_cs),
-                info_da: prepare_csv_string(info_da),
-                info_hu: prepare_csv_string(info_hu),
-                info_ja: prepare_csv_string(info_ja),
-                info_ko: prepare_csv_string(info_ko),
-                info_pl: prepare_csv_string(info_pl),
-                info_pt: prepare_csv_string(info_pt),
-                info_ru: prepare_csv_string(info_ru),
-                info_sv: prepare_csv_string(info_sv),
-                info_tr: prepare_csv_string(info_tr),
-                info_zh: prepare_csv_string(info_zh),
-            })
-        }
-        _ => Err(anyhow!("Invalid data in line {}!", i)),
-    }
-}
-
-fn prepare_csv_string(input: &str) -> Option<String> {
-    if input.trim().is_empty() {
-        None
-    } else {
-        Some(String::from(input))
-    }
+async fn record_migration(
+    conn: &mut SqliteConnection,
+    migration: &Migration,
+) -> Result<(), anyhow::Error> {
+    sqlx::query(
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+    )
+    .bind(migration.version)
+    .bind(&migration.name)
+    .execute(conn)
+    .await?;
+    Ok(())
 }
 
-/// Ensure that the correct version of sqlx-cli is installed,
-/// and install it if it isn't.
-async fn ensure_sqlx_cli_installed(ui: &mut UI<'_>) -> Result<(), anyhow::Error> {
-    /// The version of sqlx-cli required
-    const SQLX_CLI_VERSION: &str = "0.8";
-    let sqlx_version_req = VersionReq::parse(SQLX_CLI_VERSION)
-        .expect("SQLX_CLI_VERSION value is not a valid semver version requirement.");
+/// Marks the passed migrations as applied without running them.
+async fn mark_migrations_applied(
+    conn: &mut SqliteConnection,
+    migrations: &[Migration],
+) -> Result<(), anyhow::Error> {
+    let mut tx = conn.begin().await?;
+    for migration in migrations {
+        record_migration(&mut tx, migration).await?;
+    }
+    tx.commit()
+        .await
+        .context("Failed to mark migrations as applied!")?;
+    Ok(())
+}
 
-    /// Get the version of the current sqlx-cli installation, if any.
-    async fn installed_sqlx_cli_version(cargo: &str) -> Result<Option<Version>, anyhow::Error> {
-        /// The expected prefix of the version output of sqlx-cli >= 0.8
-        const SQLX_CLI_VERSION_STRING_PREFIX: &str = "sqlx-cli-sqlx";
-        /// The expected prefix of the version output of sqlx-cli < 0.8
-        const SQLX_CLI_VERSION_STRING_PREFIX_OLD: &str = "cargo-sqlx";
+/// The migration bringing databases created before migrations were introduced up to the schema
+/// `cargo db create` created at that point.
+const BASELINE_MIGRATION: i64 = 1792223807;
 
-        fn error_parsing_version() -> anyhow::Error {
-            anyhow!(
-                "Error parsing sqlx-cli version. Please install the \
-                correct version manually using `cargo install sqlx-cli \
-                --version ^{SQLX_CLI_VERSION} --locked`"
-            )
-        }
+/// Checks whether the database has a table with the passed name.
+async fn has_table(conn: &mut SqliteConnection, name: &str) -> Result<bool, anyhow::Error> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_schema WHERE type = 'table' AND name = ?")
+            .bind(name)
+            .fetch_one(conn)
+            .await?;
+    Ok(count > 0)
+}
 
-        let mut cargo_sqlx_command = {
-            let mut cmd = tokio::process::Command::new(cargo);
-            cmd.args(["sqlx", "--version"]);
-            cmd
-        };
+/// Applies all migrations that have not been applied yet, each in its own transaction.
+///
+/// Databases created before the [`BASELINE_MIGRATION`] was added, recognized by their
+/// `dataset_metadata` table, already have the schema it creates, so it is only recorded as applied
+/// for them.
+///
+/// Returns the applied migrations.
+async fn migrate<'a>(
+    conn: &mut SqliteConnection,
+    migrations: &'a [Migration],
+) -> Result<Vec<&'a Migration>, anyhow::Error> {
+    let applied: HashSet<i64> = applied_migrations(&mut *conn).await?.into_iter().collect();
 
-        let out = cargo_sqlx_command.output().await?;
-        if !out.status.success() {
-            // Failed to run the command for some reason,
-            // we conclude that sqlx-cli is not installed.
-            return Ok(None);
+    let mut newly_applied = Vec::new();
+    for migration in migrations
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        let mut tx = conn.begin().await?;
+        let has_baseline_schema = migration.version == BASELINE_MIGRATION
+            && has_table(&mut tx, "dataset_metadata").await?;
+        if has_statements(&migration.up) && !has_baseline_schema {
+            sqlx::query(&migration.up)
+                .execute(&mut *tx)
+                .await
+                .context(format!("Failed to apply migration {}!", migration))?;
         }
+        record_migration(&mut tx, migration).await?;
+        tx.commit().await?;
+        newly_applied.push(migration);
+    }
 
-        let Ok(stdout) = String::from_utf8(out.stdout) else {
-            return Err(error_parsing_version());
-        };
+    Ok(newly_applied)
+}
 
-        let Some(version) = stdout
-            .strip_prefix(SQLX_CLI_VERSION_STRING_PREFIX)
-            .or_else(|| stdout.strip_prefix(SQLX_CLI_VERSION_STRING_PREFIX_OLD))
-            .map(str::trim)
-        else {
-            return Err(error_parsing_version());
-        };
+/// Reverts the passed number of most recently applied migrations, each in its own transaction.
+///
+/// Returns the reverted migrations.
+async fn rollback<'a>(
+    conn: &mut SqliteConnection,
+    migrations: &'a [Migration],
+    steps: usize,
+) -> Result<Vec<&'a Migration>, anyhow::Error> {
+    let applied = applied_migrations(&mut *conn).await?;
 
-        let Ok(version) = Version::parse(version) else {
-            return Err(error_parsing_version());
-        };
+    let mut reverted = Vec::new();
+    for version in applied.into_iter().take(steps) {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.version == version)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Migration {} was applied but its file does not exist anymore!",
+                    version
+                )
+            })?;
+        if !has_statements(&migration.down) {
+            return Err(anyhow!(
+                "Migration {} cannot be rolled back as it has no {} section!",
+                migration,
+                MIGRATION_DOWN_MARKER
+            ));
+        }
 
-        Ok(Some(version))
+        let mut tx = conn.begin().await?;
+        sqlx::query(&migration.down)
+            .execute(&mut *tx)
+            .await
+            .context(format!("Failed to revert migration {}!", migration))?;
+        sqlx::query("DELETE FROM schema_migrations WHERE version = ?")
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        reverted.push(migration);
     }
 
-    let cargo = get_cargo_path()?;
+    Ok(reverted)
+}
 
-    let current_version = installed_sqlx_cli_version(&cargo).await?;
-    if let Some(version) = &current_version {
-        if sqlx_version_req.matches(version) {
-            // sqlx-cli is already installed and of the correct version, nothing to do
-            return Ok(());
+/// The first bytes of a gzip compressed file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The first bytes of a zstd compressed file.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Where the source data is read from.
+enum Source {
+    /// A remote file that is downloaded, e.g. the upstream dataset on GitHub
+    Url(String),
+    /// A local file, passed via `--from <path>` or configured as a `file://` URL
+    File(PathBuf),
+    /// The standard input, passed via `--from -`
+    Stdin,
+}
+
+impl Source {
+    /// Determines the source from the `--from` argument.
+    fn from_arg(from: &str) -> Self {
+        match from {
+            "-" => Source::Stdin,
+            path => Source::File(PathBuf::from(path)),
         }
     }
 
-    let curr_vers_msg = current_version
-        .map(|v| format!("The currently installed version is {v}."))
-        .unwrap_or_else(|| "sqlx-cli is currently not installed.".to_string());
-    ui.info(&format!(
-        "This command requires a version of sqlx-cli that is \
-        compatible with version {SQLX_CLI_VERSION}, which is not installed yet. \
-        {curr_vers_msg} \
-        Would you like to install the latest compatible version now? [Y/n]"
-    ));
-
-    // Read user answer
-    {
-        let mut buf = String::new();
-        let mut reader = tokio::io::BufReader::new(stdin());
-        loop {
-            reader.read_line(&mut buf).await?;
-            let line = buf.to_ascii_lowercase();
-            let line = line.trim_end();
-            if matches!(line, "" | "y" | "yes") {
-                ui.info("Starting installation of sqlx-cli...");
-                break;
-            } else if matches!(line, "n" | "no") {
-                return Err(anyhow!("Installation of sqlx-cli canceled."));
-            };
-            ui.info("Please enter y or n");
-            buf.clear();
+    /// Determines the source from a configured source's URL, pinned to its commit if configured.
+    fn configured(config: &SourceConfig) -> Result<Self, anyhow::Error> {
+        let url =
+            Url::parse(&config.url).context(format!("Invalid URL for source {}!", config.name))?;
+        if url.scheme() == "file" {
+            let path = url
+                .to_file_path()
+                .map_err(|_| anyhow!("Invalid file URL: {}!", url))?;
+            Ok(Source::File(path))
+        } else {
+            match &config.commit {
+                Some(commit) => Ok(Source::Url(pin_to_commit(&url, commit)?.to_string())),
+                None => Ok(Source::Url(config.url.clone())),
+            }
         }
     }
 
-    let mut cargo_install_command = {
-        let mut cmd = tokio::process::Command::new(&cargo);
-        cmd.args([
-            "install",
-            "sqlx-cli",
-            "--version",
-            &format!("^{SQLX_CLI_VERSION}"),
-            "--locked",
-            // Install unoptimized version,
-            // making the process much faster.
-            // sqlx-cli doesn't really need to be
-            // performant anyway for our purposes
-            "--debug",
-        ]);
-        cmd.stdout(Stdio::inherit());
-        cmd.stderr(Stdio::inherit());
-        cmd
-    };
+    /// Opens the source for reading, transparently decompressing gzip or zstd compressed data.
+    ///
+    /// The SHA-256 digest of the data is computed over the raw, possibly compressed, bytes as they
+    /// are read.
+    async fn open(&self) -> Result<SourceData, anyhow::Error> {
+        let mut etag = None;
+        let mut last_modified = None;
+        let mut total_bytes = None;
+        let reader: Box<dyn AsyncRead + Unpin + Send> = match self {
+            Source::Url(url) => {
+                let response = Client::new()
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context("Failed to download source data!")?;
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(String::from)
+                };
+                etag = header(ETAG);
+                last_modified = header(LAST_MODIFIED);
+                total_bytes = response.content_length();
+                let stream = response
+                    .bytes_stream()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+                Box::new(tokio_util::io::StreamReader::new(stream))
+            }
+            Source::File(path) => {
+                let file = File::open(path)
+                    .await
+                    .context(format!("Failed to open {}!", path.display()))?;
+                total_bytes = file.metadata().await.ok().map(|metadata| metadata.len());
+                Box::new(file)
+            }
+            Source::Stdin => Box::new(stdin()),
+        };
 
-    let mut child = cargo_install_command.spawn()?;
+        let digest = Arc::new(Mutex::new(Sha256::new()));
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let reader = InspectReader::new(reader, {
+            let digest = Arc::clone(&digest);
+            let bytes_read = Arc::clone(&bytes_read);
+            move |bytes: &[u8]| {
+                digest.lock().unwrap().update(bytes);
+                bytes_read.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+            }
+        });
 
-    let status = child.wait().await?;
-    if !status.success() {
+        Ok(SourceData {
+            reader: decompress(reader).await?,
+            etag,
+            last_modified,
+            digest,
+            bytes_read,
+            total_bytes,
+        })
+    }
+}
+
+/// The host GitHub serves raw repository files from.
+const GITHUB_RAW_HOST: &str = "raw.githubusercontent.com";
+
+/// Rewrites a raw.githubusercontent.com URL to point at the passed commit instead of a branch.
+///
+/// The URL's ref may be given either as `refs/heads/<branch>` or as a bare branch name, i.e.
+/// `https://raw.githubusercontent.com/<owner>/<repo>/<ref>/<path>`.
+fn pin_to_commit(url: &Url, commit: &str) -> Result<Url, anyhow::Error> {
+    if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
         return Err(anyhow!(
-            "Something went wrong when installing sqlx-cli. Please check output"
+            "Invalid source commit {:?}, expected a full 40 character commit SHA!",
+            commit
+        ));
+    }
+    if url.host_str() != Some(GITHUB_RAW_HOST) {
+        return Err(anyhow!(
+            "Pinning a source commit is only supported for {} URLs!",
+            GITHUB_RAW_HOST
         ));
     }
 
-    match installed_sqlx_cli_version(&cargo).await {
-        Ok(Some(v)) if sqlx_version_req.matches(&v) => {
-            ui.success(&format!("Successfully installed sqlx-cli {v}"));
-            Ok(())
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let path = match segments.as_slice() {
+        [owner, repo, "refs", "heads", _branch, path @ ..] if !path.is_empty() => {
+            [&[*owner, *repo, commit], path].concat()
         }
-        Ok(Some(v)) => Err(anyhow!("Could not update sqlx cli. Current version: {v}")),
-        Ok(None) => Err(anyhow!("sqlx-cli was not detected after installation")),
-        Err(e) => Err(e),
+        [owner, repo, _branch, path @ ..] if !path.is_empty() => {
+            [&[*owner, *repo, commit], path].concat()
+        }
+        _ => return Err(anyhow!("Unexpected source URL {}!", url)),
+    };
+
+    let mut pinned = url.clone();
+    pinned.set_path(&path.join("/"));
+    Ok(pinned)
+}
+
+/// Checks the source data has the expected digest if one is configured.
+fn verify_sha256(data: &SourceData, expected: Option<&str>) -> Result<(), anyhow::Error> {
+    match expected {
+        Some(expected) if !data.sha256().eq_ignore_ascii_case(expected.trim()) => Err(anyhow!(
+            "Source data has SHA-256 {} but {} was expected!",
+            data.sha256(),
+            expected
+        )),
+        _ => Ok(()),
     }
 }
 
-/// Find the root of the db package in the gerust workspace.
-fn db_package_root() -> Result<PathBuf, anyhow::Error> {
-    Ok(PathBuf::from(
-        std::env::var("CARGO_MANIFEST_DIR")
-            .map_err(|e| anyhow!(e).context("This command needs to be invoked using cargo"))?,
-    )
-    .join("..")
-    .join("db")
-    .canonicalize()?)
+/// The opened source data along with what is known about the version of the dataset.
+struct SourceData {
+    reader: Box<dyn AsyncRead + Unpin + Send>,
+    /// The `ETag` header the source was served with if it was downloaded
+    etag: Option<String>,
+    /// The `Last-Modified` header the source was served with if it was downloaded
+    last_modified: Option<String>,
+    digest: Arc<Mutex<Sha256>>,
+    /// The number of raw, possibly compressed, bytes read from the source so far
+    bytes_read: Arc<AtomicU64>,
+    /// The size of the source in raw bytes if known
+    total_bytes: Option<u64>,
+}
+
+impl SourceData {
+    /// The hex-encoded SHA-256 digest of the data read from the source so far.
+    fn sha256(&self) -> String {
+        format!("{:x}", self.digest.lock().unwrap().clone().finalize())
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Source::Url(url) => write!(f, "{}", url),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+/// Wraps the reader in a decoder if the data it yields is gzip or zstd compressed.
+async fn decompress(
+    reader: impl AsyncRead + Unpin + Send + 'static,
+) -> Result<Box<dyn AsyncRead + Unpin + Send>, anyhow::Error> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader
+        .fill_buf()
+        .await
+        .context("Failed to read source data!")?;
+
+    if magic.starts_with(&GZIP_MAGIC) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Ok(Box::new(decoder))
+    } else if magic.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(ZstdDecoder::new(reader)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// The sources to synchronize from along with where their data is read from.
+///
+/// If `--from` is passed, the passed file is read instead of the configured source with the
+/// highest priority, if any; the other configured sources are read as usual.
+fn sync_sources(
+    configured: &[SourceConfig],
+    args: &SyncArgs,
+) -> Result<Vec<(SourceConfig, Source)>, anyhow::Error> {
+    let replaced = args.from.as_ref().and_then(|_| {
+        configured
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, source)| source.priority)
+            .map(|(index, _)| index)
+    });
+    let mut sources = Vec::with_capacity(configured.len() + 1);
+    for (index, source) in configured.iter().enumerate() {
+        match &args.from {
+            Some(from) if replaced == Some(index) => {
+                sources.push((from_source(source.clone(), args), Source::from_arg(from)));
+            }
+            _ => sources.push((source.clone(), Source::configured(source)?)),
+        }
+    }
+    if let (Some(from), None) = (&args.from, replaced) {
+        let source = SourceConfig {
+            name: String::from("local"),
+            format: SourceFormat::default(),
+            url: String::new(),
+            priority: 0,
+            field_priorities: HashMap::new(),
+            commit: None,
+            sha256: None,
+        };
+        sources.push((from_source(source, args), Source::from_arg(from)));
+    }
+
+    if sources.is_empty() {
+        return Err(anyhow!(
+            "No sources configured – add a [[sources]] section to config/app.toml or pass --from!"
+        ));
+    }
+    let mut names = HashSet::new();
+    for (source, _) in &sources {
+        if !names.insert(source.name.as_str()) {
+            return Err(anyhow!(
+                "Source {} is configured more than once!",
+                source.name
+            ));
+        }
+        if source.name == overrides::OVERRIDES_SOURCE {
+            return Err(anyhow!(
+                "Source name {} is reserved for the overrides file!",
+                source.name
+            ));
+        }
+        if let Some(field) = source
+            .field_priorities
+            .keys()
+            .find(|field| !merge::FIELDS.contains(&field.as_str()))
+        {
+            return Err(anyhow!(
+                "Unknown field {} in field_priorities of source {}, expected one of {}!",
+                field,
+                source.name,
+                merge::FIELDS.join(", ")
+            ));
+        }
+    }
+
+    Ok(sources)
+}
+
+/// The configuration of the source whose data is read from the file passed via `--from`.
+///
+/// The file isn't the dataset the source's `commit` and `sha256` pin, so they don't apply to it.
+fn from_source(mut source: SourceConfig, args: &SyncArgs) -> SourceConfig {
+    source.commit = None;
+    source.sha256 = None;
+    if let Some(format) = args.format {
+        source.format = format;
+    }
+    source
+}
+
+/// The outcome of synchronizing the database with the source data.
+#[derive(Debug, Default, PartialEq)]
+struct SyncReport {
+    inserted: usize,
+    updated: usize,
+    deleted: usize,
+    unchanged: usize,
+    rejected: Vec<SkippedRow>,
+}
+
+/// A row of a source that was skipped because it is invalid.
+#[derive(Debug, PartialEq)]
+struct SkippedRow {
+    /// The name of the source the row was read from
+    source: String,
+    rejection: Rejection,
+}
+
+impl Display for SkippedRow {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}, {}", self.source, self.rejection)
+    }
+}
+
+/// The number of skipped rows that are listed individually in the console output.
+const MAX_REPORTED_REJECTIONS: usize = 20;
+
+fn report_rejections(ui: &mut UI<'_>, rejections: &[SkippedRow]) {
+    ui.info(&format!("Skipped {} invalid rows:", rejections.len()));
+    ui.indent();
+    for rejection in rejections.iter().take(MAX_REPORTED_REJECTIONS) {
+        ui.log(&rejection.to_string());
+    }
+    if rejections.len() > MAX_REPORTED_REJECTIONS {
+        ui.log(&format!(
+            "…and {} more",
+            rejections.len() - MAX_REPORTED_REJECTIONS
+        ));
+    }
+    ui.outdent();
+}
+
+fn report_overrides(ui: &mut UI<'_>, overrides_file: &Path, report: &OverridesReport) {
+    ui.log(&format!(
+        "Applied overrides from {}: {} patched, {} added, {} hidden",
+        overrides_file.display(),
+        report.patched,
+        report.added,
+        report.hidden
+    ));
+    if !report.stale.is_empty() {
+        ui.info(&format!(
+            "{} overrides no longer apply and can be removed:",
+            report.stale.len()
+        ));
+        ui.indent();
+        for stale in &report.stale {
+            ui.log(&stale.to_string());
+        }
+        ui.outdent();
+    }
+}
+
+/// Writes the skipped rows to a CSV file with the columns `source`, `line`, `column` and `reason`.
+async fn write_rejections(path: &Path, rejections: &[SkippedRow]) -> Result<(), anyhow::Error> {
+    let file = File::create(path)
+        .await
+        .context(format!("Failed to create {}!", path.display()))?;
+    let mut writer = AsyncWriterBuilder::new().create_writer(file);
+    writer
+        .write_record(["source", "line", "column", "reason"])
+        .await?;
+    for SkippedRow { source, rejection } in rejections {
+        writer
+            .write_record([
+                source.as_str(),
+                rejection.line.to_string().as_str(),
+                rejection.column.as_deref().unwrap_or_default(),
+                rejection.reason.as_str(),
+            ])
+            .await?;
+    }
+    writer
+        .flush()
+        .await
+        .context(format!("Failed to write {}!", path.display()))?;
+    Ok(())
+}
+
+impl SyncReport {
+    /// The number of stations merged from the sources.
+    fn total(&self) -> usize {
+        self.inserted + self.updated + self.unchanged
+    }
+}
+
+/// Synchronizes the database with the source data.
+///
+/// Each source is read completely first; the stations from all sources are then merged (see
+/// [`merge()`]) and written to the database.
+///
+/// The data is imported into a shadow copy of the database next to the configured one, which is
+/// validated and then atomically renamed over the live database. If anything fails along the
+/// way, the live database is left untouched.
+async fn sync(
+    ui: &mut UI<'_>,
+    config: &Config,
+    args: &SyncArgs,
+) -> Result<SyncReport, anyhow::Error> {
+    let sources = sync_sources(&config.sources, args)?;
+
+    let live_file = get_db_config(&config.database).get_filename().to_path_buf();
+    let shadow_file = create_shadow_db(&config.database, &live_file).await?;
+
+    // The shadow database is thrown away if anything goes wrong so there is no need for SQLite
+    // to guarantee durability while importing. It is synced to disk once before being swapped in.
+    let shadow_config = get_db_config(&config.database)
+        .filename(shadow_file.path())
+        .journal_mode(SqliteJournalMode::Memory)
+        .synchronous(SqliteSynchronous::Off)
+        .pragma("temp_store", "MEMORY")
+        .pragma("cache_size", "-65536");
+    let mut conn = SqliteConnection::connect_with(&shadow_config)
+        .await
+        .context("Failed to connect to shadow database!")?;
+
+    let mut read = Vec::new();
+    let mut metadata = Vec::new();
+    let mut rejected = Vec::new();
+    for (source_config, source) in &sources {
+        ui.log(&format!(
+            "Reading {} data from {}",
+            source_config.name, source
+        ));
+        let mut data = source.open().await?;
+        let started = Instant::now();
+        let bytes_read = Arc::clone(&data.bytes_read);
+        let progress = |rows| Progress {
+            bytes: bytes_read.load(Ordering::Relaxed),
+            total_bytes: data.total_bytes,
+            rows,
+            elapsed: started.elapsed(),
+        };
+        let mut rows = 0;
+        let stations = read_stations(
+            &mut data.reader,
+            importer(source_config.format, &source_config.name).as_ref(),
+            args.skip_invalid,
+            |rows_read| {
+                rows = rows_read;
+                ui.progress(&progress(rows));
+            },
+        )
+        .await;
+        ui.finish_progress(&progress(rows));
+        let (stations, rejections) = stations?;
+        verify_sha256(&data, source_config.sha256.as_deref()).context(format!(
+            "Unexpected data for source {}!",
+            source_config.name
+        ))?;
+
+        metadata.push(SourceMetadata::new(
+            source_config,
+            source,
+            &data,
+            stations.len(),
+        ));
+        rejected.extend(rejections.into_iter().map(|rejection| SkippedRow {
+            source: source_config.name.clone(),
+            rejection,
+        }));
+        read.push(SourceStations {
+            name: source_config.name.clone(),
+            priority: source_config.priority,
+            field_priorities: source_config.field_priorities.clone(),
+            stations,
+        });
+    }
+
+    if !rejected.is_empty() {
+        report_rejections(ui, &rejected);
+        if let Some(rejects_file) = &args.rejects_file {
+            write_rejections(rejects_file, &rejected).await?;
+            ui.log(&format!(
+                "Skipped rows written to {}",
+                rejects_file.display()
+            ));
+        }
+    }
+    let rows = read
+        .iter()
+        .map(|source| source.stations.len())
+        .sum::<usize>()
+        + rejected.len();
+    validate_rejections(&config.sync, rows, rejected.len())?;
+
+    let mut merged = merge(&read);
+    if read.len() > 1 {
+        ui.log(&format!(
+            "Merged {} stations from {} sources into {} stations ({} found in several sources)",
+            rows - rejected.len(),
+            read.len(),
+            merged.len(),
+            merged
+                .iter()
+                .filter(|station| station.sources.len() > 1)
+                .count()
+        ));
+    }
+
+    if let Some(overrides_file) = &config.sync.overrides_file {
+        let overrides = overrides::load(overrides_file).await?;
+        let overrides_report = overrides::apply(&mut merged, &overrides);
+        report_overrides(ui, overrides_file, &overrides_report);
+    }
+
+    let mut report = write_stations(&mut conn, &merged, args.batch_size).await?;
+    report.rejected = rejected;
+    stations::rebuild_search_index(&mut conn)
+        .await
+        .context("Failed to build search index!")?;
+    save_metadata(&mut conn, &metadata).await?;
+
+    validate_shadow_db(&mut conn, &config.sync, &report).await?;
+    conn.close()
+        .await
+        .context("Failed to close shadow database!")?;
+    shadow_file
+        .as_file()
+        .sync_all()
+        .context("Failed to write shadow database to disk!")?;
+
+    let keep_snapshots = config.snapshots.keep;
+    if keep_snapshots > 0 {
+        let snapshot = snapshots::create(&config.snapshots.dir, shadow_file.path()).await?;
+        ui.log(&format!("Saved snapshot {}", snapshot));
+    }
+
+    shadow_file
+        .persist(&live_file)
+        .context("Failed to replace database with shadow database!")?;
+
+    if keep_snapshots > 0 {
+        for snapshot in snapshots::prune(&config.snapshots.dir, keep_snapshots).await? {
+            ui.log(&format!("Deleted snapshot {}", snapshot));
+        }
+    }
+
+    Ok(report)
+}
+
+/// What is recorded about each source the database was synchronized from.
+struct SourceMetadata {
+    name: String,
+    priority: i64,
+    source: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    sha256: String,
+    /// The number of stations read from the source
+    row_count: usize,
+}
+
+impl SourceMetadata {
+    /// Describes the source data, which must have been read completely so its digest is final.
+    fn new(config: &SourceConfig, source: &Source, data: &SourceData, row_count: usize) -> Self {
+        SourceMetadata {
+            name: config.name.clone(),
+            priority: config.priority,
+            source: source.to_string(),
+            etag: data.etag.clone(),
+            last_modified: data.last_modified.clone(),
+            sha256: data.sha256(),
+            row_count,
+        }
+    }
+}
+
+/// Records which datasets the database was synchronized from, replacing the previous records.
+async fn save_metadata(
+    conn: &mut SqliteConnection,
+    metadata: &[SourceMetadata],
+) -> Result<(), anyhow::Error> {
+    let mut tx = conn.begin().await?;
+    sqlx::query("DELETE FROM dataset_metadata")
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete dataset metadata!")?;
+    for source in metadata {
+        sqlx::query(
+            r#"
+            INSERT INTO dataset_metadata (
+                name,
+                priority,
+                source,
+                etag,
+                last_modified,
+                sha256,
+                row_count,
+                synced_at
+            ) VALUES (
+                ?,
+                ?,
+                ?,
+                ?,
+                ?,
+                ?,
+                ?,
+                strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            )
+            "#,
+        )
+        .bind(&source.name)
+        .bind(source.priority)
+        .bind(&source.source)
+        .bind(&source.etag)
+        .bind(&source.last_modified)
+        .bind(&source.sha256)
+        .bind(source.row_count as i64)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save dataset metadata!")?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Creates a copy of the live database in a temporary file in the same directory.
+///
+/// The file is deleted automatically when the returned handle is dropped without being persisted.
+async fn create_shadow_db(
+    config: &DatabaseConfig,
+    live_file: &Path,
+) -> Result<NamedTempFile, anyhow::Error> {
+    let shadow_file = create_shadow_file(live_file).await?;
+
+    // VACUUM INTO creates a consistent copy even if the live database is being read concurrently
+    let mut conn = get_db_client(config).await;
+    sqlx::query("VACUUM INTO ?")
+        .bind(shadow_file.path().to_string_lossy().to_string())
+        .execute(&mut conn)
+        .await
+        .context("Failed to copy database into shadow database!")?;
+    conn.close().await?;
+
+    Ok(shadow_file)
+}
+
+/// Creates an empty temporary file next to the live database with the same permissions, which
+/// can be renamed over the live database atomically.
+async fn create_shadow_file(live_file: &Path) -> Result<NamedTempFile, anyhow::Error> {
+    let live_metadata = metadata(live_file)
+        .await
+        .context("Failed to read database file – make sure to run `cargo db create` first!")?;
+    let directory = match live_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = live_file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let shadow_file = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_name))
+        .suffix(".sync")
+        .tempfile_in(directory)
+        .context("Failed to create shadow database!")?;
+    set_permissions(shadow_file.path(), live_metadata.permissions())
+        .await
+        .context("Failed to set permissions of shadow database!")?;
+
+    Ok(shadow_file)
+}
+
+/// Replaces the live database with the snapshot.
+///
+/// The snapshot is copied next to the live database, checked and then atomically renamed over
+/// it, so the live database is left untouched if anything fails.
+async fn restore_snapshot(
+    config: &DatabaseConfig,
+    snapshot: &Snapshot,
+) -> Result<(), anyhow::Error> {
+    let live_file = get_db_config(config).get_filename().to_path_buf();
+    let shadow_file = create_shadow_file(&live_file).await?;
+    tokio::fs::copy(&snapshot.path, shadow_file.path())
+        .await
+        .context("Failed to copy snapshot!")?;
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(shadow_file.path())
+        .read_only(true)
+        .connect()
+        .await
+        .context("Failed to open copy of snapshot!")?;
+    validate_db_file(&mut conn).await?;
+    conn.close().await?;
+    shadow_file
+        .as_file()
+        .sync_all()
+        .context("Failed to write snapshot to disk!")?;
+
+    shadow_file
+        .persist(&live_file)
+        .context("Failed to replace database with snapshot!")?;
+    Ok(())
+}
+
+/// Lists the snapshot with the number of stations and the datasets it was synchronized from.
+async fn report_snapshot(
+    ui: &mut UI<'_>,
+    snapshot: &Snapshot,
+    live_metadata: &[DatasetMetadata],
+) -> Result<(), anyhow::Error> {
+    let contents = snapshot.contents().await?;
+    let current = !live_metadata.is_empty() && contents.metadata == live_metadata;
+    ui.log(&format!(
+        "{}{}: {} stations",
+        snapshot,
+        if current { " (current)" } else { "" },
+        contents.stations
+    ));
+    ui.indent();
+    for source in &contents.metadata {
+        ui.log(&format!(
+            "{} synced at {} from {} (SHA-256 {})",
+            source.name, source.synced_at, source.source, source.sha256
+        ));
+    }
+    ui.outdent();
+    Ok(())
+}
+
+/// Checks the shadow database is fit to replace the live database.
+async fn validate_shadow_db(
+    conn: &mut SqliteConnection,
+    config: &SyncConfig,
+    report: &SyncReport,
+) -> Result<(), anyhow::Error> {
+    validate_db_file(&mut *conn).await?;
+
+    let stations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM stations")
+        .fetch_one(&mut *conn)
+        .await?;
+    if (stations as usize) < config.min_stations {
+        return Err(anyhow!(
+            "Synchronized data contains only {} stations, expected at least {}!",
+            stations,
+            config.min_stations
+        ));
+    }
+
+    let previous_stations = report.updated + report.unchanged + report.deleted;
+    if previous_stations > 0 {
+        let deleted_ratio = report.deleted as f64 / previous_stations as f64;
+        if deleted_ratio > config.max_deleted_ratio {
+            return Err(anyhow!(
+                "Synchronization would delete {} of {} stations ({:.1}%), more than the allowed {:.1}%!",
+                report.deleted,
+                previous_stations,
+                deleted_ratio * 100.0,
+                config.max_deleted_ratio * 100.0
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the database file is intact and has the schema from `db/schema.sql`.
+async fn validate_db_file(conn: &mut SqliteConnection) -> Result<(), anyhow::Error> {
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut *conn)
+        .await?;
+    if integrity != "ok" {
+        return Err(anyhow!("Database is corrupt: {}!", integrity));
+    }
+
+    let expected_schema = load_schema(&mut reference_db().await?).await?;
+    let schema = load_schema(&mut *conn).await?;
+    if schema != expected_schema {
+        return Err(anyhow!(
+            "Database schema does not match db/schema.sql – run `cargo db migrate` to apply pending migrations!"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks no more than the allowed share of the rows read from all sources was skipped.
+fn validate_rejections(
+    config: &SyncConfig,
+    rows: usize,
+    rejected: usize,
+) -> Result<(), anyhow::Error> {
+    if rows > 0 {
+        let rejected_ratio = rejected as f64 / rows as f64;
+        if rejected_ratio > config.max_rejected_ratio {
+            return Err(anyhow!(
+                "Skipped {} of {} rows ({:.1}%), more than the allowed {:.1}%!",
+                rejected,
+                rows,
+                rejected_ratio * 100.0,
+                config.max_rejected_ratio * 100.0
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Creates an in-memory database with the schema from `db/schema.sql`.
+async fn reference_db() -> Result<SqliteConnection, anyhow::Error> {
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await?;
+    sqlx::query(read_schema().await?.as_str())
+        .execute(&mut conn)
+        .await
+        .context("Failed to create reference schema!")?;
+    Ok(conn)
+}
+
+/// Loads each table's columns with their types, sorted by table and column name.
+async fn load_schema(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<(String, String, String)>, anyhow::Error> {
+    let schema = sqlx::query_as(
+        r#"
+        SELECT
+            tables.name,
+            columns.name,
+            columns.type
+        FROM
+            sqlite_schema AS tables,
+            pragma_table_info(tables.name) AS columns
+        WHERE
+            tables.type = 'table'
+            AND tables.name NOT LIKE 'sqlite_%'
+        ORDER BY
+            tables.name,
+            columns.name
+        "#,
+    )
+    .fetch_all(conn)
+    .await
+    .context("Failed to load database schema!")?;
+    Ok(schema)
+}
+
+/// Reads all stations the importer reads from the source data.
+///
+/// `on_progress` is called with the number of rows read so far before each row is processed.
+/// Invalid rows are returned along with the stations if `skip_invalid` is set and abort reading
+/// otherwise.
+async fn read_stations<'a>(
+    reader: impl AsyncRead + Unpin + Send + 'a,
+    importer: &dyn Importer,
+    skip_invalid: bool,
+    mut on_progress: impl FnMut(u64),
+) -> Result<(Vec<StationRecord>, Vec<Rejection>), anyhow::Error> {
+    let mut records = importer.stations(Box::new(reader));
+
+    let mut stations = Vec::new();
+    let mut rejected = Vec::new();
+    let mut rows: u64 = 0;
+    while let Some(station) = records.next().await {
+        rows += 1;
+        on_progress(rows);
+        match station {
+            Ok(station) => stations.push(station),
+            Err(ReadError::Fatal(e)) => return Err(e),
+            Err(ReadError::Invalid(rejection)) if skip_invalid => rejected.push(rejection),
+            Err(ReadError::Invalid(rejection)) => {
+                return Err(anyhow!("Invalid data in {}!", rejection));
+            }
+        }
+    }
+
+    Ok((stations, rejected))
+}
+
+/// Writes the merged stations into the database the passed connection is connected to, deleting
+/// all stations that are not among them.
+///
+/// Stations are written in transactions of `batch_size` stations each. The statements are the
+/// same for every station so that they are prepared once and reused from the connection's
+/// statement cache.
+async fn write_stations(
+    conn: &mut SqliteConnection,
+    stations: &[MergedStation],
+    batch_size: NonZeroUsize,
+) -> Result<SyncReport, anyhow::Error> {
+    // every station that is still left in here after all stations have been written was removed
+    // upstream
+    let mut stale_ids: HashSet<i64> = sqlx::query_scalar("SELECT id FROM stations")
+        .fetch_all(&mut *conn)
+        .await
+        .context("Failed to load existing stations!")?
+        .into_iter()
+        .collect();
+
+    let mut report = SyncReport::default();
+    let mut tx = conn.begin().await?;
+    for (i, merged) in stations.iter().enumerate() {
+        let station = &merged.station;
+        let existed = stale_ids.remove(&station.id);
+        let station_changed = upsert_station(&mut tx, station).await?;
+        let alternative_ids_changed =
+            sync_alternative_ids(&mut tx, station.id, &station.alternative_ids).await?;
+        let provenance_changed = sync_provenance(&mut tx, station.id, &merged.provenance).await?;
+
+        if !existed {
+            report.inserted += 1;
+        } else if station_changed || alternative_ids_changed || provenance_changed {
+            report.updated += 1;
+        } else {
+            report.unchanged += 1;
+        }
+
+        if (i + 1) % batch_size == 0 {
+            tx.commit().await?;
+            tx = conn.begin().await?;
+        }
+    }
+
+    for id in stale_ids {
+        // alternative IDs and provenance are deleted via ON DELETE CASCADE
+        sqlx::query("DELETE FROM stations WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        report.deleted += 1;
+    }
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+/// Inserts the station or updates it if it exists already. Returns whether the database changed.
+async fn upsert_station(
+    conn: &mut SqliteConnection,
+    station: &StationRecord,
+) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO
+            stations
+        (
+            id,
+            name,
+            slug,
+            uic,
+            uic8_sncf,
+            latitude,
+            longitude,
+            parent_station_id,
+            country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
+            info_de,
+            info_en,
+            info_es,
+            info_fr,
+            info_it,
+            info_nb,
+            info_nl,
+            info_cs,
+            info_da,
+            info_hu,
+            info_ja,
+            info_ko,
+            info_pl,
+            info_pt,
+            info_ru,
+            info_sv,
+            info_tr,
+            info_zh
+        )
+        VALUES (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            name = excluded.name,
+            slug = excluded.slug,
+            uic = excluded.uic,
+            uic8_sncf = excluded.uic8_sncf,
+            latitude = excluded.latitude,
+            longitude = excluded.longitude,
+            parent_station_id = excluded.parent_station_id,
+            country = excluded.country,
+            time_zone = excluded.time_zone,
+            is_city = excluded.is_city,
+            is_main_station = excluded.is_main_station,
+            is_airport = excluded.is_airport,
+            is_suggestable = excluded.is_suggestable,
+            country_hint = excluded.country_hint,
+            main_station_hint = excluded.main_station_hint,
+            same_as = excluded.same_as,
+            info_de = excluded.info_de,
+            info_en = excluded.info_en,
+            info_es = excluded.info_es,
+            info_fr = excluded.info_fr,
+            info_it = excluded.info_it,
+            info_nb = excluded.info_nb,
+            info_nl = excluded.info_nl,
+            info_cs = excluded.info_cs,
+            info_da = excluded.info_da,
+            info_hu = excluded.info_hu,
+            info_ja = excluded.info_ja,
+            info_ko = excluded.info_ko,
+            info_pl = excluded.info_pl,
+            info_pt = excluded.info_pt,
+            info_ru = excluded.info_ru,
+            info_sv = excluded.info_sv,
+            info_tr = excluded.info_tr,
+            info_zh = excluded.info_zh
+        WHERE
+            (
+                stations.name,
+                stations.slug,
+                stations.uic,
+                stations.uic8_sncf,
+                stations.latitude,
+                stations.longitude,
+                stations.parent_station_id,
+                stations.country,
+                stations.time_zone,
+                stations.is_city,
+                stations.is_main_station,
+                stations.is_airport,
+                stations.is_suggestable,
+                stations.country_hint,
+                stations.main_station_hint,
+                stations.same_as,
+                stations.info_de,
+                stations.info_en,
+                stations.info_es,
+                stations.info_fr,
+                stations.info_it,
+                stations.info_nb,
+                stations.info_nl,
+                stations.info_cs,
+                stations.info_da,
+                stations.info_hu,
+                stations.info_ja,
+                stations.info_ko,
+                stations.info_pl,
+                stations.info_pt,
+                stations.info_ru,
+                stations.info_sv,
+                stations.info_tr,
+                stations.info_zh
+            ) IS NOT (
+                excluded.name,
+                excluded.slug,
+                excluded.uic,
+                excluded.uic8_sncf,
+                excluded.latitude,
+                excluded.longitude,
+                excluded.parent_station_id,
+                excluded.country,
+                excluded.time_zone,
+                excluded.is_city,
+                excluded.is_main_station,
+                excluded.is_airport,
+                excluded.is_suggestable,
+                excluded.country_hint,
+                excluded.main_station_hint,
+                excluded.same_as,
+                excluded.info_de,
+                excluded.info_en,
+                excluded.info_es,
+                excluded.info_fr,
+                excluded.info_it,
+                excluded.info_nb,
+                excluded.info_nl,
+                excluded.info_cs,
+                excluded.info_da,
+                excluded.info_hu,
+                excluded.info_ja,
+                excluded.info_ko,
+                excluded.info_pl,
+                excluded.info_pt,
+                excluded.info_ru,
+                excluded.info_sv,
+                excluded.info_tr,
+                excluded.info_zh
+            )
+        "#,
+    )
+    .bind(station.id)
+    .bind(&station.name)
+    .bind(&station.slug)
+    .bind(&station.uic)
+    .bind(&station.uic8_sncf)
+    .bind(station.latitude)
+    .bind(station.longitude)
+    .bind(station.parent_station_id)
+    .bind(&station.country)
+    .bind(&station.time_zone)
+    .bind(station.is_city)
+    .bind(station.is_main_station)
+    .bind(station.is_airport)
+    .bind(station.is_suggestable)
+    .bind(station.country_hint)
+    .bind(station.main_station_hint)
+    .bind(station.same_as)
+    .bind(&station.info_de)
+    .bind(&station.info_en)
+    .bind(&station.info_es)
+    .bind(&station.info_fr)
+    .bind(&station.info_it)
+    .bind(&station.info_nb)
+    .bind(&station.info_nl)
+    .bind(&station.info_cs)
+    .bind(&station.info_da)
+    .bind(&station.info_hu)
+    .bind(&station.info_ja)
+    .bind(&station.info_ko)
+    .bind(&station.info_pl)
+    .bind(&station.info_pt)
+    .bind(&station.info_ru)
+    .bind(&station.info_sv)
+    .bind(&station.info_tr)
+    .bind(&station.info_zh)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Makes the station's alternative IDs in the database match the passed ones. Returns whether the
+/// database changed.
+async fn sync_alternative_ids(
+    conn: &mut SqliteConnection,
+    station_id: i64,
+    alternative_ids: &[(String, String)],
+) -> Result<bool, anyhow::Error> {
+    let mut changed = false;
+
+    for (scheme, value) in alternative_ids {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO
+                alternative_ids
+            (
+                station_id,
+                scheme,
+                value
+            )
+            VALUES (
+                ?, ?, ?
+            )
+            ON CONFLICT (station_id, scheme) DO UPDATE SET
+                value = excluded.value
+            WHERE
+                alternative_ids.value IS NOT excluded.value
+            "#,
+        )
+        .bind(station_id)
+        .bind(scheme)
+        .bind(value)
+        .execute(&mut *conn)
+        .await?;
+        changed |= result.rows_affected() > 0;
+    }
+
+    let schemes = vec!["?"; alternative_ids.len()].join(", ");
+    let delete = format!(
+        "DELETE FROM alternative_ids WHERE station_id = ? AND scheme NOT IN ({})",
+        schemes
+    );
+    let mut query = sqlx::query(&delete).bind(station_id);
+    for (scheme, _) in alternative_ids {
+        query = query.bind(scheme);
+    }
+    let result = query.execute(&mut *conn).await?;
+    changed |= result.rows_affected() > 0;
+
+    Ok(changed)
+}
+
+/// Makes the station's provenance in the database match the passed one. Returns whether the
+/// database changed.
+async fn sync_provenance(
+    conn: &mut SqliteConnection,
+    station_id: i64,
+    provenance: &BTreeMap<&'static str, String>,
+) -> Result<bool, anyhow::Error> {
+    let mut changed = false;
+
+    for (field, source) in provenance {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO
+                station_provenance
+            (
+                station_id,
+                field,
+                source
+            )
+            VALUES (
+                ?, ?, ?
+            )
+            ON CONFLICT (station_id, field) DO UPDATE SET
+                source = excluded.source
+            WHERE
+                station_provenance.source IS NOT excluded.source
+            "#,
+        )
+        .bind(station_id)
+        .bind(field)
+        .bind(source)
+        .execute(&mut *conn)
+        .await?;
+        changed |= result.rows_affected() > 0;
+    }
+
+    let fields = vec!["?"; provenance.len()].join(", ");
+    let delete = format!(
+        "DELETE FROM station_provenance WHERE station_id = ? AND field NOT IN ({})",
+        fields
+    );
+    let mut query = sqlx::query(&delete).bind(station_id);
+    for field in provenance.keys() {
+        query = query.bind(field);
+    }
+    let result = query.execute(&mut *conn).await?;
+    changed |= result.rows_affected() > 0;
+
+    Ok(changed)
+}
+
+/// The number of findings per check that are listed individually in the console output.
+const MAX_REPORTED_FINDINGS: usize = 20;
+
+fn report_findings(ui: &mut UI<'_>, findings: &[Finding]) {
+    if findings.is_empty() {
+        ui.success("No problems found.");
+        return;
+    }
+
+    let mut findings_by_check: BTreeMap<Check, Vec<&Finding>> = BTreeMap::new();
+    for finding in findings {
+        findings_by_check
+            .entry(finding.check)
+            .or_default()
+            .push(finding);
+    }
+    ui.indent();
+    for (check, findings) in findings_by_check {
+        ui.info(&format!(
+            "{} ({}, {} found):",
+            check,
+            findings[0].severity,
+            findings.len()
+        ));
+        ui.indent();
+        for finding in findings.iter().take(MAX_REPORTED_FINDINGS) {
+            ui.log(&finding.message);
+        }
+        if findings.len() > MAX_REPORTED_FINDINGS {
+            ui.log(&format!(
+                "…and {} more",
+                findings.len() - MAX_REPORTED_FINDINGS
+            ));
+        }
+        ui.outdent();
+    }
+    ui.outdent();
+
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+    ui.info(&format!(
+        "Found {} errors, {} warnings and {} infos.",
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info)
+    ));
+}
+
+/// A station with its alternative IDs as it is exported, keyed by scheme.
+#[derive(Serialize)]
+struct ExportedStation<'a> {
+    #[serde(flatten)]
+    station: &'a Station,
+    alternative_ids: BTreeMap<&'a str, &'a str>,
+    /// The source each of the fields in `station_provenance` was taken from, keyed by field
+    provenance: BTreeMap<&'a str, &'a str>,
+}
+
+#[derive(Serialize)]
+struct GeoJsonFeature<'a> {
+    r#type: &'static str,
+    id: i64,
+    geometry: Option<GeoJsonPoint>,
+    properties: &'a ExportedStation<'a>,
+}
+
+#[derive(Serialize)]
+struct GeoJsonPoint {
+    r#type: &'static str,
+    /// Longitude and latitude, in that order
+    coordinates: [f64; 2],
+}
+
+impl<'a> From<&'a ExportedStation<'a>> for GeoJsonFeature<'a> {
+    fn from(exported: &'a ExportedStation<'a>) -> Self {
+        let geometry = match (exported.station.latitude, exported.station.longitude) {
+            (Some(latitude), Some(longitude)) => Some(GeoJsonPoint {
+                r#type: "Point",
+                coordinates: [longitude, latitude],
+            }),
+            _ => None,
+        };
+        GeoJsonFeature {
+            r#type: "Feature",
+            id: exported.station.id,
+            geometry,
+            properties: exported,
+        }
+    }
+}
+
+/// Writes all stations with their alternative IDs and, except for CSV, their provenance to the
+/// passed writer.
+///
+/// Returns the number of exported stations.
+async fn export(
+    conn: &mut SqliteConnection,
+    format: ExportFormat,
+    writer: impl AsyncWrite + Unpin + Send,
+) -> Result<usize, anyhow::Error> {
+    let stations = stations::load_all(&mut *conn).await?;
+    let mut alternative_ids_by_station: HashMap<i64, Vec<AlternativeId>> = HashMap::new();
+    for alternative_id in alternative_ids::load_all(&mut *conn).await? {
+        alternative_ids_by_station
+            .entry(alternative_id.station_id)
+            .or_default()
+            .push(alternative_id);
+    }
+    let provenance: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT station_id, field, source FROM station_provenance ORDER BY station_id, field",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to load station provenance!")?;
+    let mut provenance_by_station: HashMap<i64, BTreeMap<&str, &str>> = HashMap::new();
+    for (station_id, field, source) in &provenance {
+        provenance_by_station
+            .entry(*station_id)
+            .or_default()
+            .insert(field.as_str(), source.as_str());
+    }
+    let exported: Vec<ExportedStation> = stations
+        .iter()
+        .map(|station| ExportedStation {
+            station,
+            alternative_ids: alternative_ids_by_station
+                .get(&station.id)
+                .map(|alternative_ids| {
+                    alternative_ids
+                        .iter()
+                        .map(|id| (id.scheme.as_str(), id.value.as_str()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            provenance: provenance_by_station
+                .remove(&station.id)
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let mut writer = BufWriter::new(writer);
+    match format {
+        ExportFormat::Csv => write_csv(&mut writer, &exported).await?,
+        ExportFormat::Json => {
+            write_json_sequence(&mut writer, "[\n", &exported, ",\n", "\n]\n").await?
+        }
+        ExportFormat::Ndjson => {
+            for station in &exported {
+                writer.write_all(&serde_json::to_vec(station)?).await?;
+                writer.write_all(b"\n").await?;
+            }
+        }
+        ExportFormat::Geojson => {
+            let features: Vec<GeoJsonFeature> = exported.iter().map(Into::into).collect();
+            write_json_sequence(
+                &mut writer,
+                "{\"type\":\"FeatureCollection\",\"features\":[\n",
+                &features,
+                ",\n",
+                "\n]}\n",
+            )
+            .await?
+        }
+    }
+    writer.flush().await.context("Failed to write export!")?;
+
+    Ok(exported.len())
+}
+
+/// Writes the items as JSON one by one so that the export is never serialized into one big string;
+/// the items themselves are all held in memory.
+async fn write_json_sequence(
+    writer: &mut (impl AsyncWrite + Unpin),
+    prefix: &str,
+    items: &[impl Serialize],
+    separator: &str,
+    suffix: &str,
+) -> Result<(), anyhow::Error> {
+    writer.write_all(prefix.as_bytes()).await?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(separator.as_bytes()).await?;
+        }
+        writer.write_all(&serde_json::to_vec(item)?).await?;
+    }
+    writer.write_all(suffix.as_bytes()).await?;
+    Ok(())
+}
+
+/// The columns of the source data that hold a station's attributes, up to its carrier IDs.
+const CSV_STATION_COLUMNS: [&str; 16] = [
+    "id",
+    "name",
+    "slug",
+    "uic",
+    "uic8_sncf",
+    "latitude",
+    "longitude",
+    "parent_station_id",
+    "country",
+    "time_zone",
+    "is_city",
+    "is_main_station",
+    "is_airport",
+    "is_suggestable",
+    "country_hint",
+    "main_station_hint",
+];
+
+/// The languages of the source data's `info:<language>` columns.
+const CSV_INFO_LANGUAGES: [&str; 18] = [
+    "de", "en", "es", "fr", "it", "nb", "nl", "cs", "da", "hu", "ja", "ko", "pl", "pt", "ru", "sv",
+    "tr", "zh",
+];
+
+/// Writes the stations in the format of the source data with a column per alternative ID scheme.
+///
+/// Only the schemes the source data has a column for are written since the others, e.g. GTFS stop
+/// IDs, could not be synchronized back from the export.
+async fn write_csv(
+    writer: &mut (impl AsyncWrite + Unpin + Send),
+    stations: &[ExportedStation<'_>],
+) -> Result<(), anyhow::Error> {
+    let schemes: BTreeMap<&str, &str> = stations
+        .iter()
+        .flat_map(|station| station.alternative_ids.keys().copied())
+        .filter_map(|scheme| alternative_id_column(scheme).map(|column| (scheme, column)))
+        .collect();
+
+    let mut header: Vec<String> = CSV_STATION_COLUMNS.iter().map(|c| c.to_string()).collect();
+    header.extend(schemes.values().map(|column| column.to_string()));
+    header.push("same_as".to_string());
+    header.extend(
+        CSV_INFO_LANGUAGES
+            .iter()
+            .map(|language| format!("info:{}", language)),
+    );
+
+    let mut csv_writer = AsyncWriterBuilder::new()
+        .delimiter(b';')
+        .create_writer(writer);
+    csv_writer.write_record(&header).await?;
+    for exported in stations {
+        let station = exported.station;
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        let integer = |value: Option<i64>| value.map(|v| v.to_string()).unwrap_or_default();
+        let flag = |value: bool| if value { "t" } else { "f" }.to_string();
+
+        let mut record = vec![
+            station.id.to_string(),
+            station.name.clone(),
+            optional(&station.slug),
+            station.uic.clone(),
+            optional(&station.uic8_sncf),
+            number(station.latitude),
+            number(station.longitude),
+            integer(station.parent_station_id),
+            optional(&station.country),
+            optional(&station.time_zone),
+            flag(station.is_city),
+            flag(station.is_main_station),
+            flag(station.is_airport),
+            flag(station.is_suggestable),
+            flag(station.country_hint),
+            flag(station.main_station_hint),
+        ];
+        record.extend(schemes.keys().map(|scheme| {
+            exported
+                .alternative_ids
+                .get(scheme)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        }));
+        record.push(integer(station.same_as));
+        record.extend(
+            [
+                &station.info_de,
+                &station.info_en,
+                &station.info_es,
+                &station.info_fr,
+                &station.info_it,
+                &station.info_nb,
+                &station.info_nl,
+                &station.info_cs,
+                &station.info_da,
+                &station.info_hu,
+                &station.info_ja,
+                &station.info_ko,
+                &station.info_pl,
+                &station.info_pt,
+                &station.info_ru,
+                &station.info_sv,
+                &station.info_tr,
+                &station.info_zh,
+            ]
+            .map(optional),
+        );
+        csv_writer.write_record(&record).await?;
+    }
+    csv_writer.flush().await?;
+    Ok(())
+}
+
+/// The column of the source data holding the alternative IDs in the passed scheme, if any.
+fn alternative_id_column(scheme: &str) -> Option<&'static str> {
+    CARRIER_ID_COLUMNS
+        .iter()
+        .find(|(_, carrier_scheme)| *carrier_scheme == scheme)
+        .map(|(column, _)| *column)
+}
+
+fn get_db_config(config: &DatabaseConfig) -> SqliteConnectOptions {
+    let db_url = Url::parse(&config.url).expect("Invalid DATABASE_URL!");
+    ConnectOptions::from_url(&db_url).expect("Invalid DATABASE_URL!")
+}
+
+async fn get_db_client(config: &DatabaseConfig) -> SqliteConnection {
+    let db_config = get_db_config(config);
+    SqliteConnection::connect_with(&db_config).await.unwrap()
+}
+
+fn get_cargo_path() -> Result<String, anyhow::Error> {
+    std::env::var("CARGO")
+        .map_err(|_| anyhow!("Please invoke me using Cargo, e.g.: `cargo db <ARGS>`"))
+}
+
+/// Ensure that the correct version of sqlx-cli is installed,
+/// and install it if it isn't.
+async fn ensure_sqlx_cli_installed(ui: &mut UI<'_>) -> Result<(), anyhow::Error> {
+    /// The version of sqlx-cli required
+    const SQLX_CLI_VERSION: &str = "0.8";
+    let sqlx_version_req = VersionReq::parse(SQLX_CLI_VERSION)
+        .expect("SQLX_CLI_VERSION value is not a valid semver version requirement.");
+
+    /// Get the version of the current sqlx-cli installation, if any.
+    async fn installed_sqlx_cli_version(cargo: &str) -> Result<Option<Version>, anyhow::Error> {
+        /// The expected prefix of the version output of sqlx-cli >= 0.8
+        const SQLX_CLI_VERSION_STRING_PREFIX: &str = "sqlx-cli-sqlx";
+        /// The expected prefix of the version output of sqlx-cli < 0.8
+        const SQLX_CLI_VERSION_STRING_PREFIX_OLD: &str = "cargo-sqlx";
+
+        fn error_parsing_version() -> anyhow::Error {
+            anyhow!(
+                "Error parsing sqlx-cli version. Please install the \
+                correct version manually using `cargo install sqlx-cli \
+                --version ^{SQLX_CLI_VERSION} --locked`"
+            )
+        }
+
+        let mut cargo_sqlx_command = {
+            let mut cmd = tokio::process::Command::new(cargo);
+            cmd.args(["sqlx", "--version"]);
+            cmd
+        };
+
+        let out = cargo_sqlx_command.output().await?;
+        if !out.status.success() {
+            // Failed to run the command for some reason,
+            // we conclude that sqlx-cli is not installed.
+            return Ok(None);
+        }
+
+        let Ok(stdout) = String::from_utf8(out.stdout) else {
+            return Err(error_parsing_version());
+        };
+
+        let Some(version) = stdout
+            .strip_prefix(SQLX_CLI_VERSION_STRING_PREFIX)
+            .or_else(|| stdout.strip_prefix(SQLX_CLI_VERSION_STRING_PREFIX_OLD))
+            .map(str::trim)
+        else {
+            return Err(error_parsing_version());
+        };
+
+        let Ok(version) = Version::parse(version) else {
+            return Err(error_parsing_version());
+        };
+
+        Ok(Some(version))
+    }
+
+    let cargo = get_cargo_path()?;
+
+    let current_version = installed_sqlx_cli_version(&cargo).await?;
+    if let Some(version) = &current_version {
+        if sqlx_version_req.matches(version) {
+            // sqlx-cli is already installed and of the correct version, nothing to do
+            return Ok(());
+        }
+    }
+
+    let curr_vers_msg = current_version
+        .map(|v| format!("The currently installed version is {v}."))
+        .unwrap_or_else(|| "sqlx-cli is currently not installed.".to_string());
+    ui.info(&format!(
+        "This command requires a version of sqlx-cli that is \
+        compatible with version {SQLX_CLI_VERSION}, which is not installed yet. \
+        {curr_vers_msg} \
+        Would you like to install the latest compatible version now? [Y/n]"
+    ));
+
+    // Read user answer
+    {
+        let mut buf = String::new();
+        let mut reader = tokio::io::BufReader::new(stdin());
+        loop {
+            reader.read_line(&mut buf).await?;
+            let line = buf.to_ascii_lowercase();
+            let line = line.trim_end();
+            if matches!(line, "" | "y" | "yes") {
+                ui.info("Starting installation of sqlx-cli...");
+                break;
+            } else if matches!(line, "n" | "no") {
+                return Err(anyhow!("Installation of sqlx-cli canceled."));
+            };
+            ui.info("Please enter y or n");
+            buf.clear();
+        }
+    }
+
+    let mut cargo_install_command = {
+        let mut cmd = tokio::process::Command::new(&cargo);
+        cmd.args([
+            "install",
+            "sqlx-cli",
+            "--version",
+            &format!("^{SQLX_CLI_VERSION}"),
+            "--locked",
+            // Install unoptimized version,
+            // making the process much faster.
+            // sqlx-cli doesn't really need to be
+            // performant anyway for our purposes
+            "--debug",
+        ]);
+        cmd.stdout(Stdio::inherit());
+        cmd.stderr(Stdio::inherit());
+        cmd
+    };
+
+    let mut child = cargo_install_command.spawn()?;
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(anyhow!(
+            "Something went wrong when installing sqlx-cli. Please check output"
+        ));
+    }
+
+    match installed_sqlx_cli_version(&cargo).await {
+        Ok(Some(v)) if sqlx_version_req.matches(&v) => {
+            ui.success(&format!("Successfully installed sqlx-cli {v}"));
+            Ok(())
+        }
+        Ok(Some(v)) => Err(anyhow!("Could not update sqlx cli. Current version: {v}")),
+        Ok(None) => Err(anyhow!("sqlx-cli was not detected after installation")),
+        Err(e) => Err(e),
+    }
+}
+
+/// Find the root of the db package in the gerust workspace.
+fn db_package_root() -> Result<PathBuf, anyhow::Error> {
+    Ok(PathBuf::from(
+        std::env::var("CARGO_MANIFEST_DIR")
+            .map_err(|e| anyhow!(e).context("This command needs to be invoked using cargo"))?,
+    )
+    .join("..")
+    .join("db")
+    .canonicalize()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_compression::tokio::bufread::{GzipEncoder, ZstdEncoder};
+    use restations_cli::importers::trainline::TrainlineImporter;
+    use restations_config::SearchConfig;
+    use tokio::io::AsyncReadExt;
+
+    fn fixture_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stations.csv")
+    }
+
+    fn single_source(stations: Vec<StationRecord>) -> Vec<SourceStations> {
+        vec![SourceStations {
+            name: String::from("trainline"),
+            priority: 0,
+            field_priorities: HashMap::new(),
+            stations,
+        }]
+    }
+
+    async fn import_fixture(
+        reader: impl AsyncRead + Unpin + Send + 'static,
+        conn: &mut SqliteConnection,
+    ) -> SyncReport {
+        let reader = decompress(reader).await.unwrap();
+        let (stations, _) = read_stations(reader, &TrainlineImporter, false, |_| {})
+            .await
+            .unwrap();
+        write_stations(
+            conn,
+            &merge(&single_source(stations)),
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_fixture() {
+        let mut conn = reference_db().await.unwrap();
+
+        let fixture = File::open(fixture_path()).await.unwrap();
+        let report = import_fixture(fixture, &mut conn).await;
+        assert_eq!(
+            report,
+            SyncReport {
+                inserted: 3,
+                ..Default::default()
+            }
+        );
+
+        let alternative_ids: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alternative_ids")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(alternative_ids, 5);
+        let provenance: Vec<(String, String)> =
+            sqlx::query_as("SELECT DISTINCT field, source FROM station_provenance ORDER BY field")
+                .fetch_all(&mut conn)
+                .await
+                .unwrap();
+        assert_eq!(
+            provenance,
+            vec![
+                (String::from("coordinates"), String::from("trainline")),
+                (String::from("name"), String::from("trainline")),
+            ]
+        );
+
+        let fixture = File::open(fixture_path()).await.unwrap();
+        let report = import_fixture(fixture, &mut conn).await;
+        assert_eq!(
+            report,
+            SyncReport {
+                unchanged: 3,
+                ..Default::default()
+            }
+        );
+    }
+
+    async fn write_station(conn: &mut SqliteConnection, station: &StationRecord) -> SyncReport {
+        write_stations(
+            conn,
+            &merge(&single_source(vec![station.clone()])),
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_stations_twice_is_a_no_op() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+        let fixture = File::open(fixture_path()).await.unwrap();
+        let report = import_fixture(fixture, &mut conn).await;
+
+        assert_eq!(
+            report,
+            SyncReport {
+                unchanged: 3,
+                ..Default::default()
+            }
+        );
+        let alternative_ids: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alternative_ids")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(alternative_ids, 5);
+    }
+
+    #[tokio::test]
+    async fn test_write_stations_deletes_removed_stations() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+
+        let fixture = decompress(File::open(fixture_path()).await.unwrap())
+            .await
+            .unwrap();
+        let (mut stations, _) = read_stations(fixture, &TrainlineImporter, false, |_| {})
+            .await
+            .unwrap();
+        stations.retain(|station| station.id != 4916);
+        let report = write_stations(
+            &mut conn,
+            &merge(&single_source(stations)),
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report,
+            SyncReport {
+                unchanged: 2,
+                deleted: 1,
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            stations::load_by_id(4916, &mut conn).await,
+            Err(restations_db::Error::NoRecordFound)
+        ));
+        let alternative_ids: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM alternative_ids WHERE station_id = 4916")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        assert_eq!(alternative_ids, 0);
+    }
+
+    #[tokio::test]
+    async fn test_write_stations_with_overrides() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+
+        let overrides = overrides::Overrides {
+            patch: vec![overrides::StationPatch {
+                id: 8267,
+                name: Some(String::from("Lisboa Santa Apolónia Terminal")),
+                ..Default::default()
+            }],
+            hide: vec![4916],
+            ..Default::default()
+        };
+        let mut reports = vec![];
+        for _ in 0..2 {
+            let fixture = decompress(File::open(fixture_path()).await.unwrap())
+                .await
+                .unwrap();
+            let (stations, _) = read_stations(fixture, &TrainlineImporter, false, |_| {})
+                .await
+                .unwrap();
+            let mut merged = merge(&single_source(stations));
+            overrides::apply(&mut merged, &overrides);
+            reports.push(
+                write_stations(&mut conn, &merged, NonZeroUsize::new(2).unwrap())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        assert_eq!(
+            reports,
+            vec![
+                SyncReport {
+                    unchanged: 1,
+                    updated: 1,
+                    deleted: 1,
+                    ..Default::default()
+                },
+                SyncReport {
+                    unchanged: 2,
+                    ..Default::default()
+                }
+            ]
+        );
+        let lisboa = stations::load_by_id(8267, &mut conn).await.unwrap();
+        assert_eq!(lisboa.name, "Lisboa Santa Apolónia Terminal");
+    }
+
+    #[tokio::test]
+    async fn test_write_stations_compares_nulls() {
+        let mut conn = reference_db().await.unwrap();
+        let mut station = StationRecord {
+            id: 1,
+            name: String::from("Nowhere"),
+            uic: String::from("1234567"),
+            alternative_ids: vec![(String::from("sncf"), String::from("XXNOW"))],
+            ..Default::default()
+        };
+        assert_eq!(write_station(&mut conn, &station).await.inserted, 1);
+        // all optional columns are NULL, which must compare as equal to NULL
+        assert_eq!(
+            write_station(&mut conn, &station).await,
+            SyncReport {
+                unchanged: 1,
+                ..Default::default()
+            }
+        );
+
+        station.info_de = Some(String::from("Nirgendwo"));
+        assert_eq!(write_station(&mut conn, &station).await.updated, 1);
+        station.info_de = None;
+        assert_eq!(write_station(&mut conn, &station).await.updated, 1);
+        assert_eq!(write_station(&mut conn, &station).await.unchanged, 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_index() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+        stations::rebuild_search_index(&mut conn).await.unwrap();
+
+        let weights = SearchConfig::default();
+        let names = |stations: Vec<Station>| -> Vec<String> {
+            stations.into_iter().map(|station| station.name).collect()
+        };
+        let found = stations::search_by_name("PARIS ost", 10, false, &weights, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(names(found), vec!["Paris Gare de l’Est"]);
+        let found = stations::search_by_name("apolonia", 10, false, &weights, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(names(found), vec!["Lisboa Santa Apolónia"]);
+        assert!(
+            stations::search_by_name("apolnoia", 10, false, &weights, &mut conn)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let found = stations::search_by_name("apolnoia", 10, true, &weights, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(names(found), vec!["Lisboa Santa Apolónia"]);
+        assert!(
+            stations::search_by_name("\"*", 10, false, &weights, &mut conn)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        sqlx::query("UPDATE stations SET name = 'Lisboa Santa Apolónia Terminal' WHERE id = 8267")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM stations WHERE id = 4924")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        stations::rebuild_search_index(&mut conn).await.unwrap();
+        let found = stations::search_by_name("terminal", 10, false, &weights, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(names(found), vec!["Lisboa Santa Apolónia Terminal"]);
+        let found = stations::search_by_name("paris", 10, false, &weights, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(names(found), vec!["Paris Gare de l’Est"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_skip_invalid() {
+        let data = "id;name;uic;latitude;longitude\n\
+            1;Valid;8700001;48.1;2.3\n\
+            2;Invalid;8700002;north;2.3\n\
+            3;Too short\n";
+
+        let reader = decompress(std::io::Cursor::new(data.as_bytes().to_vec()))
+            .await
+            .unwrap();
+        let (stations, rejected) = read_stations(reader, &TrainlineImporter, true, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(stations.len(), 1);
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[0].line, 3);
+        assert_eq!(rejected[0].column.as_deref(), Some("latitude"));
+        assert_eq!(rejected[1].line, 4);
+        assert_eq!(rejected[1].column, None);
+
+        let reader = decompress(std::io::Cursor::new(data.as_bytes().to_vec()))
+            .await
+            .unwrap();
+        let result = read_stations(reader, &TrainlineImporter, false, |_| {}).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_compressed_fixture() {
+        let fixture = tokio::fs::read(fixture_path()).await.unwrap();
+
+        let mut gzipped = Vec::new();
+        GzipEncoder::new(&fixture[..])
+            .read_to_end(&mut gzipped)
+            .await
+            .unwrap();
+        let mut conn = reference_db().await.unwrap();
+        let report = import_fixture(std::io::Cursor::new(gzipped), &mut conn).await;
+        assert_eq!(report.inserted, 3);
+
+        let mut zstd_compressed = Vec::new();
+        ZstdEncoder::new(&fixture[..])
+            .read_to_end(&mut zstd_compressed)
+            .await
+            .unwrap();
+        let mut conn = reference_db().await.unwrap();
+        let report = import_fixture(std::io::Cursor::new(zstd_compressed), &mut conn).await;
+        assert_eq!(report.inserted, 3);
+    }
+
+    #[tokio::test]
+    async fn test_save_metadata() {
+        let config = SourceConfig {
+            name: String::from("trainline"),
+            format: SourceFormat::Trainline,
+            url: String::new(),
+            priority: 100,
+            field_priorities: HashMap::new(),
+            commit: None,
+            sha256: None,
+        };
+        let source = Source::File(fixture_path());
+        let mut data = source.open().await.unwrap();
+        let (stations, _) = read_stations(&mut data.reader, &TrainlineImporter, false, |_| {})
+            .await
+            .unwrap();
+        let mut conn = reference_db().await.unwrap();
+
+        save_metadata(
+            &mut conn,
+            &[SourceMetadata::new(&config, &source, &data, stations.len())],
+        )
+        .await
+        .unwrap();
+        save_metadata(
+            &mut conn,
+            &[SourceMetadata::new(&config, &source, &data, stations.len())],
+        )
+        .await
+        .unwrap();
+
+        let metadata: Vec<(String, i64, String, String, i64)> = sqlx::query_as(
+            "SELECT name, priority, source, sha256, row_count FROM dataset_metadata",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        let fixture = tokio::fs::read(fixture_path()).await.unwrap();
+        assert_eq!(
+            metadata,
+            vec![(
+                String::from("trainline"),
+                100,
+                fixture_path().display().to_string(),
+                format!("{:x}", Sha256::digest(&fixture)),
+                3
+            )]
+        );
+    }
+
+    #[test]
+    fn test_sync_sources_from_file() {
+        let source = |name: &str, priority: i64| SourceConfig {
+            name: String::from(name),
+            format: SourceFormat::default(),
+            url: format!("https://example.com/{}.csv", name),
+            priority,
+            field_priorities: HashMap::new(),
+            commit: None,
+            sha256: None,
+        };
+        let args = SyncArgs {
+            batch_size: NonZeroUsize::new(1000).unwrap(),
+            from: Some(String::from("stations.csv")),
+            skip_invalid: false,
+            rejects_file: None,
+            format: None,
+        };
+
+        let mut pinned = source("trainline", 100);
+        pinned.commit = Some(String::from("0123456789abcdef0123456789abcdef01234567"));
+        pinned.sha256 = Some("0".repeat(64));
+
+        let sources = sync_sources(&[source("gtfs", 10), pinned], &args).unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].0.name, "gtfs");
+        assert!(matches!(sources[0].1, Source::Url(_)));
+        assert_eq!(sources[1].0.name, "trainline");
+        assert!(matches!(&sources[1].1, Source::File(path) if path == Path::new("stations.csv")));
+        // the local file isn't the pinned dataset
+        assert_eq!(sources[1].0.commit, None);
+        assert_eq!(sources[1].0.sha256, None);
+
+        let sources = sync_sources(&[], &args).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].0.name, "local");
+    }
+
+    #[tokio::test]
+    async fn test_verify_sha256() {
+        let fixture = tokio::fs::read(fixture_path()).await.unwrap();
+        let expected = format!("{:x}", Sha256::digest(&fixture));
+        let mut data = Source::File(fixture_path()).open().await.unwrap();
+        tokio::io::copy(&mut data.reader, &mut tokio::io::sink())
+            .await
+            .unwrap();
+
+        assert!(verify_sha256(&data, None).is_ok());
+        assert!(verify_sha256(&data, Some(&expected)).is_ok());
+        assert!(verify_sha256(&data, Some(&expected.to_uppercase())).is_ok());
+        assert!(verify_sha256(&data, Some(&"0".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn test_pin_to_commit() {
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        let pinned = format!(
+            "https://raw.githubusercontent.com/trainline-eu/stations/{}/stations.csv",
+            commit
+        );
+
+        let url = Url::parse(
+            "https://raw.githubusercontent.com/trainline-eu/stations/refs/heads/master/stations.csv",
+        )
+        .unwrap();
+        assert_eq!(pin_to_commit(&url, commit).unwrap().as_str(), pinned);
+
+        let url = Url::parse(
+            "https://raw.githubusercontent.com/trainline-eu/stations/master/stations.csv",
+        )
+        .unwrap();
+        assert_eq!(pin_to_commit(&url, commit).unwrap().as_str(), pinned);
+
+        assert!(pin_to_commit(&url, "master").is_err());
+        let url = Url::parse("https://example.com/stations/master/stations.csv").unwrap();
+        assert!(pin_to_commit(&url, commit).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_csv_round_trip() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+
+        let mut exported = Vec::new();
+        let count = export(&mut conn, ExportFormat::Csv, &mut exported)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let report = import_fixture(std::io::Cursor::new(exported.clone()), &mut conn).await;
+        assert_eq!(
+            report,
+            SyncReport {
+                unchanged: 3,
+                ..Default::default()
+            }
+        );
+
+        // the source data has no column for IDs from other formats, so they are left out
+        sqlx::query(
+            "INSERT INTO alternative_ids (station_id, scheme, value) VALUES (8267, 'gtfs_regional', 'S2')",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        let mut without_gtfs = Vec::new();
+        export(&mut conn, ExportFormat::Csv, &mut without_gtfs)
+            .await
+            .unwrap();
+        assert_eq!(without_gtfs, exported);
+    }
+
+    #[tokio::test]
+    async fn test_export_json_formats() {
+        let mut conn = reference_db().await.unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut conn).await;
+
+        let mut exported = Vec::new();
+        export(&mut conn, ExportFormat::Geojson, &mut exported)
+            .await
+            .unwrap();
+        let geojson: serde_json::Value = serde_json::from_slice(&exported).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        let lisboa = features.iter().find(|f| f["id"] == 8267).unwrap();
+        assert_eq!(lisboa["geometry"]["type"], "Point");
+        assert_eq!(
+            lisboa["geometry"]["coordinates"][0],
+            lisboa["properties"]["longitude"]
+        );
+        assert_eq!(
+            lisboa["geometry"]["coordinates"][1],
+            lisboa["properties"]["latitude"]
+        );
+
+        let mut exported = Vec::new();
+        export(&mut conn, ExportFormat::Ndjson, &mut exported)
+            .await
+            .unwrap();
+        let lines: Vec<serde_json::Value> = exported
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line["alternative_ids"].is_object()));
+        assert!(lines
+            .iter()
+            .all(|line| line["provenance"]["name"] == "trainline"));
+        assert_eq!(
+            lisboa["properties"]["provenance"]["coordinates"],
+            "trainline"
+        );
+
+        let mut exported = Vec::new();
+        export(&mut conn, ExportFormat::Json, &mut exported)
+            .await
+            .unwrap();
+        let json: Vec<serde_json::Value> = serde_json::from_slice(&exported).unwrap();
+        assert_eq!(json, lines);
+    }
+
+    #[tokio::test]
+    async fn test_restore_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let live_file = dir.path().join("live.sqlite.db");
+        let config = DatabaseConfig {
+            url: format!("sqlite:{}", live_file.display()),
+        };
+        File::create(&live_file).await.unwrap();
+        let mut live = get_db_client(&config).await;
+        sqlx::query(read_schema().await.unwrap().as_str())
+            .execute(&mut live)
+            .await
+            .unwrap();
+        let fixture = File::open(fixture_path()).await.unwrap();
+        import_fixture(fixture, &mut live).await;
+        live.close().await.unwrap();
+        let snapshot = snapshots::create(&dir.path().join("snapshots"), &live_file)
+            .await
+            .unwrap();
+
+        let mut live = get_db_client(&config).await;
+        sqlx::query("DELETE FROM stations")
+            .execute(&mut live)
+            .await
+            .unwrap();
+        live.close().await.unwrap();
+        restore_snapshot(&config, &snapshot).await.unwrap();
+
+        let mut live = get_db_client(&config).await;
+        let stations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM stations")
+            .fetch_one(&mut live)
+            .await
+            .unwrap();
+        assert_eq!(stations, 3);
+
+        sqlx::query("ALTER TABLE stations ADD COLUMN foo TEXT")
+            .execute(&mut live)
+            .await
+            .unwrap();
+        live.close().await.unwrap();
+        let outdated = snapshots::create(&dir.path().join("outdated"), &live_file)
+            .await
+            .unwrap();
+        assert!(restore_snapshot(&config, &outdated).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_migration() {
+        let migration = Migration::parse(
+            "1700000000__add_stations_foo.sql",
+            "-- migrate:up\nALTER TABLE stations ADD COLUMN foo TEXT;\n\n-- migrate:down\nALTER TABLE stations DROP COLUMN foo;\n",
+        )
+        .unwrap();
+        assert_eq!(migration.version, 1700000000);
+        assert_eq!(migration.name, "add_stations_foo");
+        assert!(migration.up.contains("ADD COLUMN foo"));
+        assert!(!migration.up.contains("DROP COLUMN foo"));
+        assert!(migration.down.contains("DROP COLUMN foo"));
+
+        let migration =
+            Migration::parse("1700000000__irreversible.sql", "DELETE FROM stations;").unwrap();
+        assert!(!has_statements(&migration.down));
+
+        assert!(Migration::parse("add_stations_foo.sql", "").is_err());
+        assert!(Migration::parse("latest__add_stations_foo.sql", "").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(
+            dir.path().join("1700000001__add_stations_foo.sql"),
+            "-- migrate:up\nALTER TABLE stations ADD COLUMN foo TEXT;\n-- migrate:down\nALTER TABLE stations DROP COLUMN foo;\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            dir.path().join("1700000002__create_bars.sql"),
+            "-- migrate:up\nCREATE TABLE bars (id INTEGER);\n-- migrate:down\nDROP TABLE bars;\n",
+        )
+        .await
+        .unwrap();
+        let migrations = load_migrations(dir.path()).await.unwrap();
+        let mut conn = reference_db().await.unwrap();
+
+        let applied = migrate(&mut conn, &migrations).await.unwrap();
+        assert_eq!(applied.len(), 2);
+        assert!(migrate(&mut conn, &migrations).await.unwrap().is_empty());
+        assert_eq!(
+            applied_migrations(&mut conn).await.unwrap(),
+            vec![1700000002, 1700000001]
+        );
+        sqlx::query("SELECT foo FROM stations, bars")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let reverted = rollback(&mut conn, &migrations, 1).await.unwrap();
+        assert_eq!(reverted, vec![&migrations[1]]);
+        assert!(sqlx::query("SELECT * FROM bars")
+            .execute(&mut conn)
+            .await
+            .is_err());
+        assert_eq!(
+            applied_migrations(&mut conn).await.unwrap(),
+            vec![1700000001]
+        );
+
+        let expected_schema = load_schema(&mut reference_db().await.unwrap())
+            .await
+            .unwrap();
+        rollback(&mut conn, &migrations, 5).await.unwrap();
+        assert_eq!(load_schema(&mut conn).await.unwrap(), expected_schema);
+    }
+
+    /// The schema `cargo db create` created before versioned migrations were introduced.
+    const PRE_MIGRATIONS_SCHEMA: &str = "
+        CREATE TABLE stations (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            uic TEXT NOT NULL,
+            latitude REAL,
+            longitude REAL,
+            country TEXT,
+            info_de TEXT,
+            info_en TEXT,
+            info_es TEXT,
+            info_fr TEXT,
+            info_it TEXT,
+            info_nb TEXT,
+            info_nl TEXT,
+            info_cs TEXT,
+            info_da TEXT,
+            info_hu TEXT,
+            info_ja TEXT,
+            info_ko TEXT,
+            info_pl TEXT,
+            info_pt TEXT,
+            info_ru TEXT,
+            info_sv TEXT,
+            info_tr TEXT,
+            info_zh TEXT
+        );
+
+        CREATE UNIQUE INDEX stations_id_idx ON stations (id);
+    ";
+
+    #[tokio::test]
+    async fn test_migrate_pre_migrations_database() {
+        let migrations = load_migrations(&migrations_dir().unwrap()).await.unwrap();
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(PRE_MIGRATIONS_SCHEMA)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO stations (id, name, uic) VALUES (4916, 'Paris Gare de l’Est', '8711300')",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        let applied = migrate(&mut conn, &migrations).await.unwrap();
+
+        assert_eq!(applied.len(), migrations.len());
+        let expected_schema = load_schema(&mut reference_db().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(load_schema(&mut conn).await.unwrap(), expected_schema);
+        let is_city: bool = sqlx::query_scalar("SELECT is_city FROM stations WHERE id = 4916")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert!(!is_city);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_records_baseline_of_existing_database() {
+        let migrations = load_migrations(&migrations_dir().unwrap()).await.unwrap();
+        let mut conn = reference_db().await.unwrap();
+        // databases created before the baseline migration was added have all later ones recorded
+        ensure_migrations_table(&mut conn).await.unwrap();
+        for migration in migrations
+            .iter()
+            .filter(|migration| migration.version != BASELINE_MIGRATION)
+        {
+            record_migration(&mut conn, migration).await.unwrap();
+        }
+
+        let applied = migrate(&mut conn, &migrations).await.unwrap();
+
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].version, BASELINE_MIGRATION);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_irreversible_migration() {
+        let migrations =
+            vec![
+                Migration::parse("1700000001__irreversible.sql", "DELETE FROM stations;").unwrap(),
+            ];
+        let mut conn = reference_db().await.unwrap();
+        migrate(&mut conn, &migrations).await.unwrap();
+
+        assert!(rollback(&mut conn, &migrations, 1).await.is_err());
+        assert_eq!(
+            applied_migrations(&mut conn).await.unwrap(),
+            vec![1700000001]
+        );
+    }
 }
@@ -251,7 +251,17 @@ async fn generate_migration(name: String) -> Result<String, anyhow::Error> {
     let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
     let file_name = format!("{}__{}.sql", timestamp.as_secs(), name);
     let path = format!("./db/migrations/{}", file_name);
-    create_project_file(&path, "".as_bytes())?;
+
+    let template = get_liquid_template("migration/file.sql")?;
+    let variables = liquid::object!({
+        "name": name
+    });
+    let output = template
+        .render(&variables)
+        .context("Failed to render Liquid template")?;
+
+    fs::create_dir_all("./db/migrations").context("Could not create db/migrations directory!")?;
+    create_project_file(&path, output.as_bytes())?;
 
     Ok(path)
 }
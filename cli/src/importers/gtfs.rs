@@ -0,0 +1,281 @@
+use super::{
+    hashed_station_id, prepare_csv_string, read_csv_record, scheme, Importer, ReadError, Rejection,
+    SourceReader, StationRecord, StationStream,
+};
+use anyhow::{anyhow, Context};
+use csv_async::{AsyncReaderBuilder, StringRecord, Trim};
+use futures::future;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use tokio::io::AsyncReadExt;
+
+/// The file in the feed that holds the stops.
+const STOPS_FILE: &str = "stops.txt";
+
+/// The columns that must be present in `stops.txt`'s header.
+const REQUIRED_COLUMNS: [&str; 2] = ["stop_id", "stop_name"];
+
+/// The prefix of the scheme of the alternative ID that holds the stop's ID in the feed. Stop IDs
+/// are only unique within a feed, so the prefix is followed by the source's name, e.g.
+/// `urn:x_gtfs_regional:1234`.
+pub const GTFS_SCHEME_PREFIX: &str = "gtfs";
+
+/// The byte order mark `stops.txt` may start with.
+const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+
+/// Reads the stops from a GTFS feed, see
+/// <https://gtfs.org/documentation/schedule/reference/#stopstxt>.
+///
+/// Stops (`location_type` 0) and stations (`location_type` 1) are imported while entrances, generic
+/// nodes and boarding areas are ignored. Stops that belong to a station, e.g. platforms, are not
+/// suggestable.
+///
+/// Stops don't have a UIC, so the `uic` column is left empty and the stop's ID is stored as an
+/// alternative ID in the source's scheme instead (see [`GTFS_SCHEME_PREFIX`]). The station's ID is
+/// derived from the source's name and the stop's ID (see [`hashed_station_id`]).
+pub struct GtfsImporter {
+    /// The name of the source the feed is read from
+    source: String,
+}
+
+impl GtfsImporter {
+    pub fn new(source: &str) -> Self {
+        GtfsImporter {
+            source: source.to_string(),
+        }
+    }
+}
+
+impl Importer for GtfsImporter {
+    fn stations<'a>(&self, mut reader: SourceReader<'a>) -> StationStream<'a> {
+        let source = self.source.clone();
+        stream::once(async move {
+            // zip files can only be read once they are complete since their index is at the end
+            let mut feed = Vec::new();
+            reader
+                .read_to_end(&mut feed)
+                .await
+                .context("Failed to read GTFS feed!")
+                .map_err(ReadError::Fatal)?;
+            let stops = tokio::task::spawn_blocking(move || read_stops_file(feed))
+                .await
+                .map_err(|e| ReadError::Fatal(e.into()))?
+                .map_err(ReadError::Fatal)?;
+
+            let mut rdr = AsyncReaderBuilder::new()
+                .trim(Trim::All)
+                .create_reader(Cursor::new(stops));
+            let headers = rdr
+                .headers()
+                .await
+                .context("Failed to read header from stops.txt!")
+                .map_err(ReadError::Fatal)?;
+            let positions: HashMap<String, usize> = headers
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.to_string(), i))
+                .collect();
+            for column in REQUIRED_COLUMNS {
+                if !positions.contains_key(column) {
+                    return Err(ReadError::Fatal(anyhow!(
+                        "Missing column {} in stops.txt!",
+                        column
+                    )));
+                }
+            }
+
+            Ok(rdr.into_records().filter_map(move |record| {
+                future::ready(read_stop(record, &positions, &source).transpose())
+            }))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+/// Extracts `stops.txt` from the zip file holding the feed.
+fn read_stops_file(feed: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(feed)).context("GTFS feed is not a zip file!")?;
+    let mut file = archive
+        .by_name(STOPS_FILE)
+        .context("GTFS feed does not contain stops.txt!")?;
+    let mut stops = Vec::new();
+    file.read_to_end(&mut stops)
+        .context("Failed to extract stops.txt from GTFS feed!")?;
+    if stops.starts_with(&UTF8_BOM) {
+        stops.drain(..UTF8_BOM.len());
+    }
+    Ok(stops)
+}
+
+/// Maps a row of the passed source's `stops.txt` to a station. Returns `None` for rows that are no
+/// stops or stations.
+fn read_stop(
+    record: Result<StringRecord, csv_async::Error>,
+    positions: &HashMap<String, usize>,
+    source: &str,
+) -> Result<Option<StationRecord>, ReadError> {
+    let (record, line) = read_csv_record(record)?;
+    let reject = |column: &str, reason: String| {
+        ReadError::Invalid(Rejection {
+            line,
+            column: Some(column.to_string()),
+            reason,
+        })
+    };
+    let optional = |column: &str| {
+        positions
+            .get(column)
+            .and_then(|position| record.get(*position))
+            .and_then(prepare_csv_string)
+    };
+    let required = |column: &str| {
+        optional(column).ok_or_else(|| reject(column, String::from("missing value")))
+    };
+    let coordinate = |column: &str| {
+        optional(column)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|e| reject(column, format!("{:?} is not a number ({})", value, e)))
+            })
+            .transpose()
+    };
+
+    match optional("location_type").as_deref() {
+        None | Some("0") | Some("1") => {}
+        // entrances, generic nodes and boarding areas
+        Some("2") | Some("3") | Some("4") => return Ok(None),
+        Some(other) => {
+            return Err(reject(
+                "location_type",
+                format!("{:?} is not a location type", other),
+            ))
+        }
+    }
+
+    let stop_id = required("stop_id")?;
+    let parent_station = optional("parent_station");
+    let station_id = |stop_id: &str| hashed_station_id(&format!("{}:{}", source, stop_id));
+
+    Ok(Some(StationRecord {
+        id: station_id(&stop_id),
+        name: required("stop_name")?,
+        latitude: coordinate("stop_lat")?,
+        longitude: coordinate("stop_lon")?,
+        parent_station_id: parent_station.as_deref().map(station_id),
+        time_zone: optional("stop_timezone"),
+        is_suggestable: parent_station.is_none(),
+        alternative_ids: vec![(
+            format!("{}_{}", GTFS_SCHEME_PREFIX, scheme(source)),
+            stop_id,
+        )],
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn feed(stops: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("agency.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .write_all(b"agency_id,agency_name\n1,Example\n")
+            .unwrap();
+        writer
+            .start_file(STOPS_FILE, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(stops.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    async fn read(feed: Vec<u8>) -> Vec<Result<StationRecord, ReadError>> {
+        GtfsImporter::new("regional")
+            .stations(Box::new(Cursor::new(feed)))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_read_stops() {
+        let feed = feed(
+            "\u{feff}stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+            STA1,Central Station,48.1,11.5,1,\n\
+            P1,\"Central Station, Platform 1\",48.1001,11.5001,0,STA1\n\
+            E1,Central Station North Entrance,48.102,11.5,2,STA1\n\
+            S2,Market Square,,,,\n\
+            S3,Broken,north,11.5,0,\n",
+        );
+
+        let stations = read(feed).await;
+
+        assert_eq!(stations.len(), 4);
+        let station = stations[0].as_ref().unwrap();
+        assert_eq!(station.id, hashed_station_id("regional:STA1"));
+        assert_eq!(station.name, "Central Station");
+        assert_eq!(station.uic, "");
+        assert_eq!(station.latitude, Some(48.1));
+        assert!(station.is_suggestable);
+        assert_eq!(
+            station.alternative_ids,
+            vec![(String::from("gtfs_regional"), String::from("STA1"))]
+        );
+
+        let platform = stations[1].as_ref().unwrap();
+        assert_eq!(platform.name, "Central Station, Platform 1");
+        assert_eq!(platform.parent_station_id, Some(station.id));
+        assert!(!platform.is_suggestable);
+
+        let stop = stations[2].as_ref().unwrap();
+        assert_eq!(stop.name, "Market Square");
+        assert_eq!(stop.latitude, None);
+
+        assert!(matches!(
+            &stations[3],
+            Err(ReadError::Invalid(Rejection { line: 6, column: Some(column), .. })) if column == "stop_lat"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_stops_of_several_feeds() {
+        let feed = feed("stop_id,stop_name\n1,Central Station\n");
+
+        let regional: Vec<_> = GtfsImporter::new("regional")
+            .stations(Box::new(Cursor::new(feed.clone())))
+            .collect()
+            .await;
+        let city: Vec<_> = GtfsImporter::new("City Bus")
+            .stations(Box::new(Cursor::new(feed)))
+            .collect()
+            .await;
+
+        // stop IDs are only unique within a feed
+        let (regional, city) = (regional[0].as_ref().unwrap(), city[0].as_ref().unwrap());
+        assert_ne!(regional.id, city.id);
+        assert_eq!(
+            regional.alternative_ids,
+            vec![(String::from("gtfs_regional"), String::from("1"))]
+        );
+        assert_eq!(
+            city.alternative_ids,
+            vec![(String::from("gtfs_city_bus"), String::from("1"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_invalid_feed() {
+        let stations = read(b"stop_id,stop_name\n1,Not zipped\n".to_vec()).await;
+        assert!(matches!(stations.as_slice(), [Err(ReadError::Fatal(_))]));
+
+        let stations = read(feed("stop_id,stop_lat,stop_lon\n1,48.1,11.5\n")).await;
+        assert!(matches!(stations.as_slice(), [Err(ReadError::Fatal(_))]));
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::anyhow;
+use csv_async::StringRecord;
+use futures::stream::BoxStream;
+use restations_config::SourceFormat;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use tokio::io::AsyncRead;
+
+/// Imports GTFS feeds
+pub mod gtfs;
+/// Imports NeTEx stop places
+pub mod netex;
+/// Imports Trainline EU's stations dataset
+pub mod trainline;
+
+/// The source data as it is passed to an [`Importer`].
+pub type SourceReader<'a> = Box<dyn AsyncRead + Unpin + Send + 'a>;
+
+/// The stations an [`Importer`] reads from the source data, in the order they appear in it.
+pub type StationStream<'a> = BoxStream<'a, Result<StationRecord, ReadError>>;
+
+/// Reads stations from source data in a particular format.
+///
+/// Importers only map the source data to [`StationRecord`]s. Writing the stations to the
+/// database, keeping track of changes and skipping invalid rows is the same for all formats.
+pub trait Importer {
+    /// Returns the stations in the passed source data.
+    ///
+    /// Rows that cannot be mapped to a station are yielded as [`ReadError::Invalid`] so they can be
+    /// skipped. Once a [`ReadError::Fatal`] was yielded, the stream must not be polled anymore.
+    fn stations<'a>(&self, reader: SourceReader<'a>) -> StationStream<'a>;
+}
+
+/// Returns the [`Importer`] for the data of the source with the passed name in the passed format.
+pub fn importer(format: SourceFormat, source: &str) -> Box<dyn Importer> {
+    match format {
+        SourceFormat::Trainline => Box::new(trainline::TrainlineImporter),
+        SourceFormat::Gtfs => Box::new(gtfs::GtfsImporter::new(source)),
+        SourceFormat::Netex => Box::new(netex::NetexImporter::new(source)),
+    }
+}
+
+/// A station as it is read from the source data, with the columns of the `stations` table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StationRecord {
+    pub id: i64,
+    pub name: String,
+    pub slug: Option<String>,
+    pub uic: String,
+    pub uic8_sncf: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub parent_station_id: Option<i64>,
+    pub country: Option<String>,
+    pub time_zone: Option<String>,
+    pub is_city: bool,
+    pub is_main_station: bool,
+    pub is_airport: bool,
+    pub is_suggestable: bool,
+    pub country_hint: bool,
+    pub main_station_hint: bool,
+    pub same_as: Option<i64>,
+    pub info_de: Option<String>,
+    pub info_en: Option<String>,
+    pub info_es: Option<String>,
+    pub info_fr: Option<String>,
+    pub info_it: Option<String>,
+    pub info_nb: Option<String>,
+    pub info_nl: Option<String>,
+    pub info_cs: Option<String>,
+    pub info_da: Option<String>,
+    pub info_hu: Option<String>,
+    pub info_ja: Option<String>,
+    pub info_ko: Option<String>,
+    pub info_pl: Option<String>,
+    pub info_pt: Option<String>,
+    pub info_ru: Option<String>,
+    pub info_sv: Option<String>,
+    pub info_tr: Option<String>,
+    pub info_zh: Option<String>,
+    pub alternative_ids: Vec<(String, String)>,
+}
+
+impl StationRecord {
+    /// Returns the field holding the station's name in the passed language, e.g. `info_de` for
+    /// "de" or "de-CH", if the language is supported.
+    pub fn info_mut(&mut self, lang: &str) -> Option<&mut Option<String>> {
+        let lang = lang.split(['-', '_']).next().unwrap_or_default();
+        match lang.to_ascii_lowercase().as_str() {
+            "de" => Some(&mut self.info_de),
+            "en" => Some(&mut self.info_en),
+            "es" => Some(&mut self.info_es),
+            "fr" => Some(&mut self.info_fr),
+            "it" => Some(&mut self.info_it),
+            "nb" | "no" => Some(&mut self.info_nb),
+            "nl" => Some(&mut self.info_nl),
+            "cs" => Some(&mut self.info_cs),
+            "da" => Some(&mut self.info_da),
+            "hu" => Some(&mut self.info_hu),
+            "ja" => Some(&mut self.info_ja),
+            "ko" => Some(&mut self.info_ko),
+            "pl" => Some(&mut self.info_pl),
+            "pt" => Some(&mut self.info_pt),
+            "ru" => Some(&mut self.info_ru),
+            "sv" => Some(&mut self.info_sv),
+            "tr" => Some(&mut self.info_tr),
+            "zh" => Some(&mut self.info_zh),
+            _ => None,
+        }
+    }
+}
+
+/// A row of the source data that was skipped because it is invalid.
+#[derive(Debug, PartialEq)]
+pub struct Rejection {
+    /// The line in the source data the row starts at
+    pub line: u64,
+    /// The column that holds invalid data if the problem is with a particular column
+    pub column: Option<String>,
+    /// Why the row is invalid
+    pub reason: String,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match &self.column {
+            Some(column) => write!(f, "line {}, column {}: {}", self.line, column, self.reason),
+            None => write!(f, "line {}: {}", self.line, self.reason),
+        }
+    }
+}
+
+/// Why a row of the source data could not be read.
+#[derive(Debug)]
+pub enum ReadError {
+    /// The source data cannot be read any further, e.g. because the connection was interrupted
+    Fatal(anyhow::Error),
+    /// The row contains invalid data
+    Invalid(Rejection),
+}
+
+/// Unwraps a record read from a CSV file, returning it with the line it starts at.
+fn read_csv_record(
+    record: Result<StringRecord, csv_async::Error>,
+) -> Result<(StringRecord, u64), ReadError> {
+    match record {
+        Ok(record) => {
+            let line = record
+                .position()
+                .map(|position| position.line())
+                .unwrap_or_default();
+            Ok((record, line))
+        }
+        Err(e) if e.is_io_error() => Err(ReadError::Fatal(
+            anyhow!(e).context("Failed to read record from CSV file!"),
+        )),
+        Err(e) => Err(ReadError::Invalid(Rejection {
+            line: e
+                .position()
+                .map(|position| position.line())
+                .unwrap_or_default(),
+            column: None,
+            reason: e.to_string(),
+        })),
+    }
+}
+
+/// Derives a station ID from an identifier in the source data that is not an integer, e.g. a GTFS
+/// stop ID.
+///
+/// The identifier is hashed into a positive integer that is stable across synchronizations.
+pub fn hashed_station_id(id: &str) -> i64 {
+    let digest = Sha256::digest(id.as_bytes());
+    let bytes: [u8; 8] = digest[..8]
+        .try_into()
+        .expect("SHA-256 digests are 32 bytes");
+    i64::from_be_bytes(bytes) & i64::MAX
+}
+
+/// Turns a name into an alternative ID scheme, e.g. `imported-id` into `imported_id`.
+fn scheme(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Returns the value unless it is empty or only consists of whitespace.
+fn prepare_csv_string(input: &str) -> Option<String> {
+    if input.trim().is_empty() {
+        None
+    } else {
+        Some(String::from(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashed_station_id_is_stable() {
+        assert_eq!(hashed_station_id("STA1"), hashed_station_id("STA1"));
+        assert_ne!(hashed_station_id("STA1"), hashed_station_id("STA2"));
+        assert!(hashed_station_id("STA1") >= 0);
+    }
+}
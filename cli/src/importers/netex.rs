@@ -0,0 +1,481 @@
+use super::{
+    hashed_station_id, scheme, Importer, ReadError, Rejection, SourceReader, StationRecord,
+    StationStream,
+};
+use anyhow::Context;
+use futures::stream::{self, StreamExt};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::VecDeque;
+use tokio::io::BufReader;
+
+/// The prefix of the schemes of the alternative IDs read from NeTEx files. The identifiers are only
+/// known to be unique within a source, so the prefix is followed by the source's name, e.g.
+/// `urn:x_netex_regional:NSR:StopPlace:337` for the element's NeTEx ID.
+pub const NETEX_SCHEME_PREFIX: &str = "netex";
+
+/// The key of the alternative ID that holds a `PrivateCode` without a `type`.
+const PRIVATE_CODE_KEY: &str = "private_code";
+
+/// Reads the `StopPlace` and `Quay` elements from a NeTEx file, see <https://netex-cen.eu>.
+///
+/// Each stop place and each of its quays becomes a station, with the quays being children of their
+/// stop place that are not suggestable. Names in `alternativeNames` are imported as the station's
+/// names in the respective languages.
+///
+/// The element's NeTEx ID is stored as an alternative ID in the source's scheme (see
+/// [`NETEX_SCHEME_PREFIX`]) and the station's ID is derived from the source's name and the NeTEx ID
+/// (see [`hashed_station_id`]). Entries in `keyList` and `PrivateCode`s are stored as alternative
+/// IDs in schemes named after the source and their key or type, e.g. `netex_regional_imported_id`,
+/// except for those holding the UIC (`uicCode` keys or private codes of type `uic`), which is
+/// stored as the station's UIC.
+pub struct NetexImporter {
+    /// The name of the source the file is read from
+    source: String,
+}
+
+impl NetexImporter {
+    pub fn new(source: &str) -> Self {
+        NetexImporter {
+            source: source.to_string(),
+        }
+    }
+}
+
+impl Importer for NetexImporter {
+    fn stations<'a>(&self, reader: SourceReader<'a>) -> StationStream<'a> {
+        let state = NetexReader {
+            source: self.source.clone(),
+            reader: Reader::from_reader(BufReader::new(reader)),
+            buf: Vec::new(),
+            line: 1,
+            pending: VecDeque::new(),
+            done: false,
+        };
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(station) = state.pending.pop_front() {
+                    return Some((station, state));
+                }
+                if state.done {
+                    return None;
+                }
+                match state.next_stop_place().await {
+                    Ok(Some((stop_place, line))) => {
+                        state
+                            .pending
+                            .extend(read_stop_place(&stop_place, &state.source, line));
+                    }
+                    Ok(None) => state.done = true,
+                    Err(e) => {
+                        state.done = true;
+                        return Some((Err(ReadError::Fatal(e)), state));
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Reads the `StopPlace` elements from the XML one by one.
+struct NetexReader<'a> {
+    /// The name of the source the file is read from
+    source: String,
+    reader: Reader<BufReader<SourceReader<'a>>>,
+    buf: Vec<u8>,
+    /// The line the reader is at, counted from the newlines in the events read so far
+    line: u64,
+    /// The stations read from the last stop place that have not been yielded yet
+    pending: VecDeque<Result<StationRecord, ReadError>>,
+    done: bool,
+}
+
+impl NetexReader<'_> {
+    /// Reads the next `StopPlace` element, returning it with the line it starts at.
+    async fn next_stop_place(&mut self) -> Result<Option<(Element, u64)>, anyhow::Error> {
+        let mut stack: Vec<Element> = Vec::new();
+        let mut start_line = self.line;
+        loop {
+            self.buf.clear();
+            let event = self
+                .reader
+                .read_event_into_async(&mut self.buf)
+                .await
+                .context(format!("Failed to parse NeTEx XML in line {}!", self.line))?;
+            let newlines = match &event {
+                Event::Start(e) | Event::Empty(e) => count_newlines(e),
+                Event::Text(e) => count_newlines(e),
+                Event::CData(e) => count_newlines(e),
+                Event::Comment(e) => count_newlines(e),
+                _ => 0,
+            };
+
+            match event {
+                Event::Start(e) if !stack.is_empty() || e.local_name().as_ref() == b"StopPlace" => {
+                    if stack.is_empty() {
+                        start_line = self.line;
+                    }
+                    stack.push(Element::from_start(&e));
+                }
+                Event::Empty(e) if !stack.is_empty() => {
+                    let element = Element::from_start(&e);
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                Event::Text(e) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&e.unescape()?);
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&String::from_utf8_lossy(&e));
+                    }
+                }
+                Event::End(_) if !stack.is_empty() => {
+                    let element = stack.pop().expect("the stack is not empty");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => {
+                            self.line += newlines;
+                            return Ok(Some((element, start_line)));
+                        }
+                    }
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+            self.line += newlines;
+        }
+    }
+}
+
+fn count_newlines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|byte| **byte == b'\n').count() as u64
+}
+
+/// An XML element with its children, ignoring namespaces.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn from_start(start: &BytesStart) -> Self {
+        Element {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+            attributes: start
+                .attributes()
+                .flatten()
+                .map(|attribute| {
+                    (
+                        String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+                        attribute
+                            .unescape_value()
+                            .map(|value| value.to_string())
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn children<'e>(&'e self, name: &'e str) -> impl Iterator<Item = &'e Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Returns the descendant at the passed path of element names.
+    fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter().try_fold(self, |element, name| {
+            element.children.iter().find(|child| child.name == *name)
+        })
+    }
+
+    /// Returns the element's text unless it is empty.
+    fn text(&self) -> Option<String> {
+        let text = self.text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
+
+/// Maps a `StopPlace` element of the passed source to a station followed by a station for each of
+/// its quays.
+fn read_stop_place(
+    stop_place: &Element,
+    source: &str,
+    line: u64,
+) -> Vec<Result<StationRecord, ReadError>> {
+    let station = read_place(stop_place, None, source, line);
+    let quays = stop_place
+        .find(&["quays"])
+        .map(|quays| quays.children("Quay").collect::<Vec<_>>())
+        .unwrap_or_default();
+    let name = station.as_ref().ok().map(|station| station.name.clone());
+
+    let mut stations = vec![station];
+    for quay in quays {
+        stations.push(read_place(
+            quay,
+            Some((stop_place, name.as_deref())),
+            source,
+            line,
+        ));
+    }
+    stations
+}
+
+/// Maps a `StopPlace` or, if a parent is passed, a `Quay` element to a station.
+fn read_place(
+    element: &Element,
+    parent: Option<(&Element, Option<&str>)>,
+    source: &str,
+    line: u64,
+) -> Result<StationRecord, ReadError> {
+    let reject = |column: &str, reason: String| {
+        ReadError::Invalid(Rejection {
+            line,
+            column: Some(column.to_string()),
+            reason,
+        })
+    };
+    let coordinate = |name: &str| {
+        element
+            .find(&["Centroid", "Location", name])
+            .and_then(Element::text)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|e| reject(name, format!("{:?} is not a number ({})", value, e)))
+            })
+            .transpose()
+    };
+
+    let netex_id = element
+        .attribute("id")
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| reject("id", format!("{} has no ID", element.name)))?;
+    // quays usually don't have names of their own
+    let name = element
+        .find(&["Name"])
+        .and_then(Element::text)
+        .or_else(|| parent.and_then(|(_, name)| name.map(str::to_string)))
+        .ok_or_else(|| reject("Name", String::from("missing value")))?;
+
+    let station_id = |netex_id: &str| hashed_station_id(&format!("{}:{}", source, netex_id));
+    let source_scheme = format!("{}_{}", NETEX_SCHEME_PREFIX, scheme(source));
+
+    let mut station = StationRecord {
+        id: station_id(netex_id),
+        name,
+        latitude: coordinate("Latitude")?,
+        longitude: coordinate("Longitude")?,
+        is_suggestable: parent.is_none(),
+        alternative_ids: vec![(source_scheme.clone(), netex_id.to_string())],
+        ..Default::default()
+    };
+    station.parent_station_id = match parent {
+        Some((stop_place, _)) => stop_place.attribute("id").map(station_id),
+        None => element
+            .find(&["ParentSiteRef"])
+            .and_then(|parent| parent.attribute("ref"))
+            .map(station_id),
+    };
+
+    if let Some(alternative_names) = element.find(&["alternativeNames"]) {
+        for alternative_name in alternative_names.children("AlternativeName") {
+            let Some(name) = alternative_name.find(&["Name"]) else {
+                continue;
+            };
+            let lang = name.attribute("lang").unwrap_or_default();
+            if let (Some(info), Some(text)) = (station.info_mut(lang), name.text()) {
+                info.get_or_insert(text);
+            }
+        }
+    }
+
+    let key_values = element
+        .find(&["keyList"])
+        .map(|key_list| key_list.children("KeyValue").collect::<Vec<_>>())
+        .unwrap_or_default();
+    let identifiers = key_values
+        .into_iter()
+        .filter_map(|key_value| {
+            let key = key_value.find(&["Key"]).and_then(Element::text)?;
+            let value = key_value.find(&["Value"]).and_then(Element::text)?;
+            Some((key, value))
+        })
+        .chain(element.children("PrivateCode").filter_map(|private_code| {
+            let key = private_code
+                .attribute("type")
+                .unwrap_or(PRIVATE_CODE_KEY)
+                .to_string();
+            Some((key, private_code.text()?))
+        }));
+    for (key, value) in identifiers {
+        let key = scheme(&key);
+        if key == "uic" || key == "uic_code" || key == "uiccode" {
+            if station.uic.is_empty() {
+                station.uic = value;
+            }
+            continue;
+        }
+        // keys are only meaningful within the source, e.g. a platform's private code
+        let scheme = format!("{}_{}", source_scheme, key);
+        if !station
+            .alternative_ids
+            .iter()
+            .any(|(existing, _)| *existing == scheme)
+        {
+            station.alternative_ids.push((scheme, value));
+        }
+    }
+
+    Ok(station)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const STOP_PLACES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<PublicationDelivery xmlns="http://www.netex.org.uk/netex" version="1.15">
+  <dataObjects>
+    <SiteFrame id="NSR:SiteFrame:1" version="1">
+      <stopPlaces>
+        <StopPlace id="NSR:StopPlace:337" version="1">
+          <keyList>
+            <KeyValue>
+              <Key>uicCode</Key>
+              <Value>7600100</Value>
+            </KeyValue>
+            <KeyValue>
+              <Key>imported-id</Key>
+              <Value>NSB:StopArea:OSL</Value>
+            </KeyValue>
+          </keyList>
+          <Name lang="no">Oslo S</Name>
+          <alternativeNames>
+            <AlternativeName>
+              <NameType>translation</NameType>
+              <Name lang="en">Oslo Central Station</Name>
+            </AlternativeName>
+            <AlternativeName>
+              <NameType>translation</NameType>
+              <Name lang="de">Oslo Hauptbahnhof &amp; Zentrum</Name>
+            </AlternativeName>
+          </alternativeNames>
+          <PrivateCode type="jbv">OSL</PrivateCode>
+          <Centroid>
+            <Location>
+              <Longitude>10.753276</Longitude>
+              <Latitude>59.910925</Latitude>
+            </Location>
+          </Centroid>
+          <StopPlaceType>railStation</StopPlaceType>
+          <quays>
+            <Quay id="NSR:Quay:1" version="1">
+              <Centroid>
+                <Location>
+                  <Longitude>10.7531</Longitude>
+                  <Latitude>59.9110</Latitude>
+                </Location>
+              </Centroid>
+              <PublicCode>1</PublicCode>
+            </Quay>
+          </quays>
+        </StopPlace>
+        <StopPlace id="NSR:StopPlace:2" version="1">
+          <Centroid>
+            <Location>
+              <Longitude>east</Longitude>
+              <Latitude>59.9</Latitude>
+            </Location>
+          </Centroid>
+        </StopPlace>
+        <netex:StopPlace xmlns:netex="http://www.netex.org.uk/netex" id="NSR:StopPlace:3" version="1">
+          <netex:Name>Nationaltheatret</netex:Name>
+          <netex:ParentSiteRef ref="NSR:StopPlace:337"/>
+        </netex:StopPlace>
+      </stopPlaces>
+    </SiteFrame>
+  </dataObjects>
+</PublicationDelivery>
+"#;
+
+    async fn read(xml: &str) -> Vec<Result<StationRecord, ReadError>> {
+        NetexImporter::new("regional")
+            .stations(Box::new(Cursor::new(xml.as_bytes().to_vec())))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_read_stop_places() {
+        let stations = read(STOP_PLACES).await;
+
+        assert_eq!(stations.len(), 4);
+        let station = stations[0].as_ref().unwrap();
+        assert_eq!(station.id, hashed_station_id("regional:NSR:StopPlace:337"));
+        assert_eq!(station.name, "Oslo S");
+        assert_eq!(station.uic, "7600100");
+        assert_eq!(station.latitude, Some(59.910925));
+        assert_eq!(station.longitude, Some(10.753276));
+        assert_eq!(station.info_en.as_deref(), Some("Oslo Central Station"));
+        assert_eq!(
+            station.info_de.as_deref(),
+            Some("Oslo Hauptbahnhof & Zentrum")
+        );
+        assert!(station.is_suggestable);
+        assert_eq!(
+            station.alternative_ids,
+            vec![
+                (
+                    String::from("netex_regional"),
+                    String::from("NSR:StopPlace:337")
+                ),
+                (
+                    String::from("netex_regional_imported_id"),
+                    String::from("NSB:StopArea:OSL")
+                ),
+                (String::from("netex_regional_jbv"), String::from("OSL")),
+            ]
+        );
+
+        let quay = stations[1].as_ref().unwrap();
+        assert_eq!(quay.id, hashed_station_id("regional:NSR:Quay:1"));
+        assert_eq!(quay.name, "Oslo S");
+        assert_eq!(quay.parent_station_id, Some(station.id));
+        assert_eq!(quay.latitude, Some(59.911));
+        assert!(!quay.is_suggestable);
+
+        assert!(matches!(
+            &stations[2],
+            Err(ReadError::Invalid(Rejection { line: 48, column: Some(column), .. })) if column == "Name"
+        ));
+
+        let child = stations[3].as_ref().unwrap();
+        assert_eq!(child.name, "Nationaltheatret");
+        assert_eq!(child.parent_station_id, Some(station.id));
+    }
+
+    #[tokio::test]
+    async fn test_read_malformed_xml() {
+        let stations = read("<StopPlace id=\"1\"><Name>Oslo S</Nam></StopPlace>").await;
+
+        assert!(matches!(stations.as_slice(), [Err(ReadError::Fatal(_))]));
+    }
+}
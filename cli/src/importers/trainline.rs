@@ -0,0 +1,319 @@
+use super::{
+    prepare_csv_string, read_csv_record, Importer, ReadError, Rejection, SourceReader,
+    StationRecord, StationStream,
+};
+use anyhow::{anyhow, Context};
+use csv_async::{AsyncReaderBuilder, StringRecord, Trim};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+
+/// Reads Trainline EU's stations dataset, see <https://github.com/trainline-eu/stations>.
+///
+/// Columns are matched by name. The `*_is_enabled` columns, which tell whether a carrier sells
+/// tickets for a station, `sncf_self_service_machine` and `normalised_code` are intentionally not
+/// imported: they describe Trainline's ticketing rather than the station, and name searches use
+/// their own normalized keys. Columns the importer doesn't know are ignored as well.
+pub struct TrainlineImporter;
+
+impl Importer for TrainlineImporter {
+    fn stations<'a>(&self, reader: SourceReader<'a>) -> StationStream<'a> {
+        let mut rdr = AsyncReaderBuilder::new()
+            .trim(Trim::All)
+            .delimiter(b';')
+            .create_reader(reader);
+        stream::once(async move {
+            let headers = rdr
+                .headers()
+                .await
+                .context("Failed to read header from CSV file!")
+                .map_err(ReadError::Fatal)?;
+            let columns = Columns::from_headers(headers).map_err(ReadError::Fatal)?;
+            Ok(rdr
+                .into_records()
+                .map(move |record| read_station(record, &columns)))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+/// The columns that must be present in the source data's header.
+const REQUIRED_COLUMNS: [&str; 5] = ["id", "name", "uic", "latitude", "longitude"];
+
+/// The columns holding the station's ID in a carrier's or vendor's scheme, with their scheme.
+///
+/// Columns missing from the source data are skipped; new upstream columns need to be added here to
+/// be imported.
+pub const CARRIER_ID_COLUMNS: [(&str, &str); 22] = [
+    ("sncf_id", "sncf"),
+    ("sncf_tvs_id", "sncf_tvs"),
+    ("entur_id", "entur"),
+    ("db_id", "db"),
+    ("busbud_id", "busbud"),
+    ("distribusion_id", "distribusion"),
+    ("flixbus_id", "flixbus"),
+    ("cff_id", "cff"),
+    ("leoexpress_id", "leoexpress"),
+    ("obb_id", "obb"),
+    ("ouigo_id", "ouigo"),
+    ("trenitalia_id", "trenitalia"),
+    ("trenitalia_rtvt_id", "trenitalia_rtvt"),
+    ("trenord_id", "trenord"),
+    ("ntv_rtiv_id", "ntv_rtiv"),
+    ("ntv_id", "ntv"),
+    ("hkx_id", "hkx"),
+    ("renfe_id", "renfe"),
+    ("atoc_id", "atoc"),
+    ("benerail_id", "benerail"),
+    ("westbahn_id", "westbahn"),
+    ("iata_airport_code", "iata"),
+];
+
+/// Maps the column names in the source data's header to their position in each record.
+///
+/// Columns are always looked up by name so that reordering or adding columns upstream does not
+/// affect the import.
+struct Columns {
+    positions: HashMap<String, usize>,
+    alternative_ids: Vec<(String, usize)>,
+}
+
+impl Columns {
+    fn from_headers(headers: &StringRecord) -> Result<Self, anyhow::Error> {
+        let positions: HashMap<String, usize> = headers
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), i))
+            .collect();
+
+        for column in REQUIRED_COLUMNS {
+            if !positions.contains_key(column) {
+                return Err(anyhow!("Missing column {} in source data!", column));
+            }
+        }
+
+        let alternative_ids: Vec<(String, usize)> = CARRIER_ID_COLUMNS
+            .iter()
+            .filter_map(|(column, scheme)| {
+                positions
+                    .get(*column)
+                    .map(|position| (scheme.to_string(), *position))
+            })
+            .collect();
+
+        Ok(Columns {
+            positions,
+            alternative_ids,
+        })
+    }
+
+    fn get<'r>(&self, record: &'r StringRecord, column: &str) -> Option<&'r str> {
+        self.positions
+            .get(column)
+            .and_then(|position| record.get(*position))
+    }
+
+    fn alternative_ids(&self, record: &StringRecord) -> Vec<(String, String)> {
+        self.alternative_ids
+            .iter()
+            .filter_map(|(scheme, position)| {
+                record
+                    .get(*position)
+                    .and_then(prepare_csv_string)
+                    .map(|value| (scheme.clone(), value))
+            })
+            .collect()
+    }
+}
+
+fn read_station(
+    record: Result<StringRecord, csv_async::Error>,
+    columns: &Columns,
+) -> Result<StationRecord, ReadError> {
+    let (record, line) = read_csv_record(record)?;
+    prepare_station(&record, columns, line).map_err(ReadError::Invalid)
+}
+
+fn prepare_station(
+    record: &StringRecord,
+    columns: &Columns,
+    line: u64,
+) -> Result<StationRecord, Rejection> {
+    let reject = |column: &str, reason: String| Rejection {
+        line,
+        column: Some(column.to_string()),
+        reason,
+    };
+    let required = |column: &str| {
+        columns
+            .get(record, column)
+            .ok_or_else(|| reject(column, String::from("missing value")))
+    };
+    let optional = |column: &str| columns.get(record, column).and_then(prepare_csv_string);
+    let integer = |column: &str, value: &str| {
+        value
+            .parse::<i64>()
+            .map_err(|e| reject(column, format!("{:?} is not an integer ({})", value, e)))
+    };
+    let coordinate = |column: &str| {
+        let value = required(column)?;
+        if value.trim().is_empty() {
+            Ok(None)
+        } else {
+            value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|e| reject(column, format!("{:?} is not a number ({})", value, e)))
+        }
+    };
+    let flag = |column: &str| {
+        prepare_csv_bool(columns.get(record, column).unwrap_or_default())
+            .map_err(|e| reject(column, e.to_string()))
+    };
+
+    let id = integer("id", required("id")?)?;
+    let lat = coordinate("latitude")?;
+    let lon = coordinate("longitude")?;
+    let parent_station_id = optional("parent_station_id")
+        .map(|parent_station_id| integer("parent_station_id", &parent_station_id))
+        .transpose()?;
+    let same_as = optional("same_as")
+        .map(|same_as| integer("same_as", &same_as))
+        .transpose()?;
+
+    Ok(StationRecord {
+        id,
+        name: required("name")?.to_string(),
+        slug: optional("slug"),
+        uic: required("uic")?.to_string(),
+        uic8_sncf: optional("uic8_sncf"),
+        latitude: lat,
+        longitude: lon,
+        parent_station_id,
+        country: optional("country"),
+        time_zone: optional("time_zone"),
+        is_city: flag("is_city")?,
+        is_main_station: flag("is_main_station")?,
+        is_airport: flag("is_airport")?,
+        is_suggestable: flag("is_suggestable")?,
+        country_hint: flag("country_hint")?,
+        main_station_hint: flag("main_station_hint")?,
+        same_as,
+        info_de: optional("info:de"),
+        info_en: optional("info:en"),
+        info_es: optional("info:es"),
+        info_fr: optional("info:fr"),
+        info_it: optional("info:it"),
+        info_nb: optional("info:nb"),
+        info_nl: optional("info:nl"),
+        info_cs: optional("info:cs"),
+        info_da: optional("info:da"),
+        info_hu: optional("info:hu"),
+        info_ja: optional("info:ja"),
+        info_ko: optional("info:ko"),
+        info_pl: optional("info:pl"),
+        info_pt: optional("info:pt"),
+        info_ru: optional("info:ru"),
+        info_sv: optional("info:sv"),
+        info_tr: optional("info:tr"),
+        info_zh: optional("info:zh"),
+        alternative_ids: columns.alternative_ids(record),
+    })
+}
+
+fn prepare_csv_bool(input: &str) -> Result<bool, anyhow::Error> {
+    match input.trim() {
+        "t" | "true" | "1" => Ok(true),
+        "f" | "false" | "0" | "" => Ok(false),
+        other => Err(anyhow!("{:?} is not a boolean", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_station_matches_columns_by_name() {
+        let headers = StringRecord::from(vec![
+            "uic",
+            "longitude",
+            "name",
+            "is_main_station",
+            "id",
+            "latitude",
+            "info:fr",
+        ]);
+        let columns = Columns::from_headers(&headers).unwrap();
+        let record = StringRecord::from(vec![
+            "8727100",
+            "2.355",
+            "Paris Gare du Nord",
+            "t",
+            "4916",
+            "48.88",
+            "",
+        ]);
+
+        let station = prepare_station(&record, &columns, 0).unwrap();
+
+        assert_eq!(station.id, 4916);
+        assert_eq!(station.name, "Paris Gare du Nord");
+        assert_eq!(station.uic, "8727100");
+        assert_eq!(station.latitude, Some(48.88));
+        assert_eq!(station.longitude, Some(2.355));
+        assert!(station.is_main_station);
+        assert!(!station.is_city);
+        assert_eq!(station.info_fr, None);
+        assert_eq!(station.time_zone, None);
+    }
+
+    #[test]
+    fn test_prepare_station_collects_carrier_ids() {
+        let headers = StringRecord::from(vec![
+            "id",
+            "name",
+            "uic",
+            "latitude",
+            "longitude",
+            "parent_station_id",
+            "sncf_id",
+            "sncf_is_enabled",
+            "db_id",
+            "iata_airport_code",
+            "station_group_id",
+        ]);
+        let columns = Columns::from_headers(&headers).unwrap();
+        let record = StringRecord::from(vec![
+            "4916",
+            "Paris Gare de l’Est",
+            "8711300",
+            "",
+            "",
+            "4924",
+            "FRPST",
+            "t",
+            "",
+            "PAR",
+            "17",
+        ]);
+
+        let station = prepare_station(&record, &columns, 0).unwrap();
+
+        // neither `parent_station_id` nor other columns ending in `_id` are carrier IDs
+        assert_eq!(
+            station.alternative_ids,
+            vec![
+                (String::from("sncf"), String::from("FRPST")),
+                (String::from("iata"), String::from("PAR")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_columns_require_mandatory_columns() {
+        let headers = StringRecord::from(vec!["id", "name", "latitude", "longitude"]);
+
+        assert!(Columns::from_headers(&headers).is_err());
+    }
+}
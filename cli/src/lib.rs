@@ -1,4 +1,14 @@
 //! The restations-cli crate implements the project's CLI tools `db` and `generate` as well as contains functionality for displaying information in a console UI.
 
+/// Reading stations from source data in the supported formats
+pub mod importers;
+/// Data quality checks for the stations dataset
+pub mod lint;
+/// Merging the stations read from several sources
+pub mod merge;
+/// Local corrections applied on top of the stations dataset when synchronizing
+pub mod overrides;
+/// Copies of the synchronized database that can be restored later
+pub mod snapshots;
 /// Utilities for CLIs
 pub mod util;
@@ -0,0 +1,502 @@
+use clap::ValueEnum;
+use restations_db::entities::stations::Station;
+use restations_db::search::search_key;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// How severe a problem found in the dataset is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Something that might be intended but is worth a look
+    Info,
+    /// Data that is likely wrong
+    Warning,
+    /// Data that is definitely wrong
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The checks run against the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    /// Several stations other than cities have the same UIC
+    DuplicateUic,
+    /// A station has no latitude or longitude
+    MissingCoordinates,
+    /// A station is located at latitude 0, longitude 0
+    NullIsland,
+    /// A station's coordinates are outside its country
+    OutsideCountry,
+    /// The UIC country code of a station's UIC does not match its country
+    UicCountryMismatch,
+    /// Several stations have names that only differ in case or diacritics
+    SimilarNames,
+}
+
+impl Check {
+    /// The severity of the problems found by this check.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Check::DuplicateUic | Check::NullIsland => Severity::Error,
+            Check::MissingCoordinates | Check::OutsideCountry | Check::UicCountryMismatch => {
+                Severity::Warning
+            }
+            Check::SimilarNames => Severity::Info,
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Check::DuplicateUic => write!(f, "duplicate-uic"),
+            Check::MissingCoordinates => write!(f, "missing-coordinates"),
+            Check::NullIsland => write!(f, "null-island"),
+            Check::OutsideCountry => write!(f, "outside-country"),
+            Check::UicCountryMismatch => write!(f, "uic-country-mismatch"),
+            Check::SimilarNames => write!(f, "similar-names"),
+        }
+    }
+}
+
+/// A problem found in the dataset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    /// The stations the problem concerns
+    pub station_ids: Vec<i64>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(check: Check, station_ids: Vec<i64>, message: String) -> Self {
+        Finding {
+            check,
+            severity: check.severity(),
+            station_ids,
+            message,
+        }
+    }
+}
+
+/// How far, in degrees, coordinates may be outside a country's bounding box before they are
+/// reported. This compensates for the bounding boxes being approximate.
+const COUNTRY_BOUNDS_MARGIN: f64 = 0.5;
+
+/// Approximate bounding boxes of the countries in the dataset as
+/// `(country, min latitude, max latitude, min longitude, max longitude)`. Overseas territories are
+/// not included, except for the Canary and Balearic Islands.
+const COUNTRY_BOUNDS: [(&str, f64, f64, f64, f64); 42] = [
+    ("AD", 42.43, 42.66, 1.41, 1.79),
+    ("AL", 39.60, 42.70, 19.20, 21.10),
+    ("AT", 46.37, 49.02, 9.53, 17.16),
+    ("BA", 42.55, 45.28, 15.72, 19.62),
+    ("BE", 49.50, 51.51, 2.54, 6.41),
+    ("BG", 41.23, 44.22, 22.36, 28.61),
+    ("BY", 51.26, 56.17, 23.18, 32.78),
+    ("CH", 45.82, 47.81, 5.96, 10.49),
+    ("CZ", 48.55, 51.06, 12.09, 18.86),
+    ("DE", 47.27, 55.06, 5.87, 15.04),
+    ("DK", 54.56, 57.75, 8.07, 15.20),
+    ("EE", 57.52, 59.68, 21.76, 28.21),
+    ("ES", 27.60, 43.79, -18.20, 4.33),
+    ("FI", 59.80, 70.09, 20.55, 31.59),
+    ("FR", 41.33, 51.09, -5.14, 9.56),
+    ("GB", 49.86, 60.86, -8.65, 1.77),
+    ("GR", 34.80, 41.75, 19.37, 29.65),
+    ("HR", 42.39, 46.55, 13.49, 19.45),
+    ("HU", 45.74, 48.59, 16.11, 22.90),
+    ("IE", 51.42, 55.39, -10.48, -5.99),
+    ("IT", 35.49, 47.09, 6.63, 18.52),
+    ("LI", 47.05, 47.27, 9.47, 9.64),
+    ("LT", 53.90, 56.45, 20.93, 26.84),
+    ("LU", 49.44, 50.18, 5.73, 6.53),
+    ("LV", 55.67, 58.09, 20.97, 28.24),
+    ("MC", 43.72, 43.75, 7.40, 7.44),
+    ("MD", 45.47, 48.49, 26.62, 30.13),
+    ("ME", 41.85, 43.56, 18.43, 20.36),
+    ("MK", 40.85, 42.37, 20.45, 23.04),
+    ("NL", 50.75, 53.56, 3.36, 7.23),
+    ("NO", 57.96, 71.19, 4.60, 31.10),
+    ("PL", 49.00, 54.84, 14.12, 24.15),
+    ("PT", 36.96, 42.15, -9.50, -6.19),
+    ("RO", 43.62, 48.27, 20.26, 29.76),
+    ("RS", 42.23, 46.19, 18.82, 23.01),
+    ("SE", 55.34, 69.06, 11.11, 24.17),
+    ("SI", 45.42, 46.88, 13.38, 16.61),
+    ("SK", 47.73, 49.61, 16.83, 22.57),
+    ("SM", 43.89, 43.99, 12.40, 12.52),
+    ("TR", 35.80, 42.11, 25.66, 44.82),
+    ("UA", 44.39, 52.38, 22.14, 40.23),
+    ("XK", 41.85, 43.27, 20.01, 21.79),
+];
+
+/// The UIC country codes, i.e. the first two digits of UICs, used in each country. Some countries
+/// have several codes, e.g. for different infrastructure managers.
+const UIC_COUNTRY_CODES: [(&str, &[&str]); 44] = [
+    ("AL", &["41"]),
+    ("AT", &["81"]),
+    ("BA", &["44", "49", "50"]),
+    ("BE", &["88"]),
+    ("BG", &["52"]),
+    ("BY", &["21"]),
+    ("CH", &["85"]),
+    ("CN", &["33"]),
+    ("CZ", &["54"]),
+    ("DE", &["80"]),
+    ("DK", &["86"]),
+    ("DZ", &["92"]),
+    ("EE", &["26"]),
+    ("ES", &["71"]),
+    ("FI", &["10"]),
+    ("FR", &["87"]),
+    ("GB", &["70"]),
+    ("GE", &["28"]),
+    ("GR", &["73"]),
+    ("HR", &["78"]),
+    ("HU", &["55"]),
+    ("IE", &["60"]),
+    ("IT", &["83"]),
+    ("JP", &["42"]),
+    ("KZ", &["27"]),
+    ("LI", &["81", "85"]),
+    ("LT", &["24"]),
+    ("LU", &["82"]),
+    ("LV", &["25"]),
+    ("MA", &["93"]),
+    ("MC", &["87"]),
+    ("MD", &["23"]),
+    ("ME", &["62"]),
+    ("MK", &["65"]),
+    ("NL", &["84"]),
+    ("NO", &["76"]),
+    ("PL", &["51"]),
+    ("PT", &["94"]),
+    ("RO", &["53"]),
+    ("RS", &["72"]),
+    ("RU", &["20"]),
+    ("SE", &["74"]),
+    ("SI", &["79"]),
+    ("SK", &["56"]),
+];
+
+/// Runs all checks against the passed stations.
+///
+/// The findings are ordered by check and, within each check, by the first affected station.
+pub fn lint(stations: &[Station]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    findings.extend(check_duplicate_uics(stations));
+    findings.extend(stations.iter().filter_map(check_coordinates));
+    findings.extend(stations.iter().filter_map(check_country_bounds));
+    findings.extend(stations.iter().filter_map(check_uic_country_code));
+    findings.extend(check_similar_names(stations));
+    findings.sort_by(|a, b| (a.check, &a.station_ids).cmp(&(b.check, &b.station_ids)));
+    findings
+}
+
+fn check_duplicate_uics(stations: &[Station]) -> Vec<Finding> {
+    let mut stations_by_uic: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    // stations without a UIC, e.g. stops imported from GTFS feeds, don't conflict, and neither do
+    // cities, which usually share the UIC of their main station
+    for station in stations
+        .iter()
+        .filter(|station| !station.uic.is_empty() && !station.is_city)
+    {
+        stations_by_uic
+            .entry(station.uic.as_str())
+            .or_default()
+            .push(station.id);
+    }
+    stations_by_uic
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(uic, ids)| {
+            let message = format!("UIC {} is used by {} stations", uic, ids.len());
+            Finding::new(Check::DuplicateUic, ids, message)
+        })
+        .collect()
+}
+
+fn check_coordinates(station: &Station) -> Option<Finding> {
+    match (station.latitude, station.longitude) {
+        (Some(latitude), Some(longitude)) if latitude == 0.0 && longitude == 0.0 => {
+            Some(Finding::new(
+                Check::NullIsland,
+                vec![station.id],
+                format!("{} is located at 0,0", describe(station)),
+            ))
+        }
+        (Some(_), Some(_)) => None,
+        _ => Some(Finding::new(
+            Check::MissingCoordinates,
+            vec![station.id],
+            format!("{} has no coordinates", describe(station)),
+        )),
+    }
+}
+
+fn check_country_bounds(station: &Station) -> Option<Finding> {
+    let (latitude, longitude) = (station.latitude?, station.longitude?);
+    if latitude == 0.0 && longitude == 0.0 {
+        return None;
+    }
+    let country = station.country.as_deref()?;
+    let (_, min_latitude, max_latitude, min_longitude, max_longitude) = COUNTRY_BOUNDS
+        .iter()
+        .find(|(bounds_country, ..)| *bounds_country == country)?;
+
+    let within = |value: f64, min: f64, max: f64| {
+        value >= min - COUNTRY_BOUNDS_MARGIN && value <= max + COUNTRY_BOUNDS_MARGIN
+    };
+    if within(latitude, *min_latitude, *max_latitude)
+        && within(longitude, *min_longitude, *max_longitude)
+    {
+        None
+    } else {
+        Some(Finding::new(
+            Check::OutsideCountry,
+            vec![station.id],
+            format!(
+                "{} is located at {},{} which is outside {}",
+                describe(station),
+                latitude,
+                longitude,
+                country
+            ),
+        ))
+    }
+}
+
+fn check_uic_country_code(station: &Station) -> Option<Finding> {
+    let country = station.country.as_deref()?;
+    let (_, codes) = UIC_COUNTRY_CODES
+        .iter()
+        .find(|(codes_country, _)| *codes_country == country)?;
+    let code = station.uic.get(0..2)?;
+
+    if codes.contains(&code) {
+        None
+    } else {
+        Some(Finding::new(
+            Check::UicCountryMismatch,
+            vec![station.id],
+            format!(
+                "{} has UIC country code {} but is located in {} (expected {})",
+                describe(station),
+                code,
+                country,
+                codes.join(" or ")
+            ),
+        ))
+    }
+}
+
+fn check_similar_names(stations: &[Station]) -> Vec<Finding> {
+    let mut stations_by_name: BTreeMap<String, Vec<&Station>> = BTreeMap::new();
+    for station in stations {
+        stations_by_name
+            .entry(search_key(&station.name))
+            .or_default()
+            .push(station);
+    }
+    stations_by_name
+        .into_values()
+        .filter_map(|stations| {
+            let names: BTreeSet<&str> = stations.iter().map(|s| s.name.as_str()).collect();
+            if names.len() < 2 {
+                return None;
+            }
+            let names: Vec<String> = names.iter().map(|name| format!("{:?}", name)).collect();
+            Some(Finding::new(
+                Check::SimilarNames,
+                stations.iter().map(|station| station.id).collect(),
+                format!(
+                    "Names only differ in case or diacritics: {}",
+                    names.join(", ")
+                ),
+            ))
+        })
+        .collect()
+}
+
+fn describe(station: &Station) -> String {
+    format!("{} ({})", station.name, station.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station(
+        id: i64,
+        name: &str,
+        uic: &str,
+        country: &str,
+        position: Option<(f64, f64)>,
+    ) -> Station {
+        Station {
+            id,
+            name: name.to_string(),
+            slug: None,
+            uic: uic.to_string(),
+            uic8_sncf: None,
+            latitude: position.map(|(latitude, _)| latitude),
+            longitude: position.map(|(_, longitude)| longitude),
+            parent_station_id: None,
+            country: Some(country.to_string()),
+            time_zone: None,
+            is_city: false,
+            is_main_station: false,
+            is_airport: false,
+            is_suggestable: true,
+            country_hint: false,
+            main_station_hint: false,
+            same_as: None,
+            info_de: None,
+            info_en: None,
+            info_es: None,
+            info_fr: None,
+            info_it: None,
+            info_nb: None,
+            info_nl: None,
+            info_cs: None,
+            info_da: None,
+            info_hu: None,
+            info_ja: None,
+            info_ko: None,
+            info_pl: None,
+            info_pt: None,
+            info_ru: None,
+            info_sv: None,
+            info_tr: None,
+            info_zh: None,
+        }
+    }
+
+    fn checks(findings: &[Finding]) -> Vec<(Check, Vec<i64>)> {
+        findings
+            .iter()
+            .map(|finding| (finding.check, finding.station_ids.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_stations() {
+        let stations = vec![
+            station(
+                1,
+                "Paris Gare de l’Est",
+                "8711300",
+                "FR",
+                Some((48.876742, 2.358424)),
+            ),
+            station(
+                2,
+                "Lisboa Santa Apolónia",
+                "9430007",
+                "PT",
+                Some((38.71387, -9.122271)),
+            ),
+        ];
+
+        assert_eq!(lint(&stations), vec![]);
+    }
+
+    #[test]
+    fn test_lint_allows_cities_sharing_uics() {
+        let mut city = station(1, "Lisboa", "9430007", "PT", Some((38.71667, -9.13333)));
+        city.is_city = true;
+        let mut main_station = station(
+            2,
+            "Lisboa Santa Apolónia",
+            "9430007",
+            "PT",
+            Some((38.71387, -9.122271)),
+        );
+        main_station.is_main_station = true;
+        let other = station(
+            3,
+            "Lisboa Oriente",
+            "9430007",
+            "PT",
+            Some((38.76779, -9.09871)),
+        );
+
+        let mut stations = vec![city, main_station];
+        assert_eq!(lint(&stations), vec![]);
+
+        stations.push(other);
+        assert_eq!(
+            checks(&lint(&stations)),
+            vec![(Check::DuplicateUic, vec![2, 3])]
+        );
+    }
+
+    #[test]
+    fn test_lint_finds_problems() {
+        let stations = vec![
+            station(
+                1,
+                "Paris Gare de l’Est",
+                "8711300",
+                "FR",
+                Some((48.876742, 2.358424)),
+            ),
+            station(2, "Paris Est", "8711300", "FR", Some((48.876742, 2.358424))),
+            station(3, "Nowhere", "8700003", "FR", None),
+            station(4, "Null Island", "8700004", "FR", Some((0.0, 0.0))),
+            station(
+                5,
+                "Lisbon in France",
+                "8700005",
+                "FR",
+                Some((38.71387, -9.122271)),
+            ),
+            station(6, "Porto", "8700006", "PT", Some((41.148, -8.585))),
+            station(
+                7,
+                "Lisboa Santa Apolónia",
+                "9430007",
+                "PT",
+                Some((38.71387, -9.122271)),
+            ),
+            station(
+                8,
+                "LISBOA SANTA APOLONIA",
+                "9430008",
+                "PT",
+                Some((38.71387, -9.122271)),
+            ),
+        ];
+
+        let findings = lint(&stations);
+
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (Check::DuplicateUic, vec![1, 2]),
+                (Check::MissingCoordinates, vec![3]),
+                (Check::NullIsland, vec![4]),
+                (Check::OutsideCountry, vec![5]),
+                (Check::UicCountryMismatch, vec![6]),
+                (Check::SimilarNames, vec![7, 8]),
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert_eq!(findings[5].severity, Severity::Info);
+    }
+}
@@ -0,0 +1,599 @@
+use crate::importers::trainline::CARRIER_ID_COLUMNS;
+use crate::importers::{hashed_station_id, StationRecord};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The fields whose value is taken from a single source when merging, see
+/// [`SourceStations::field_priorities`]. `coordinates` covers the latitude and longitude,
+/// `flags` the `is_*` and `*_hint` columns and `info` the names in all languages.
+pub const FIELDS: [&str; 11] = [
+    "name",
+    "slug",
+    "uic",
+    "uic8_sncf",
+    "coordinates",
+    "parent_station",
+    "country",
+    "time_zone",
+    "flags",
+    "same_as",
+    "info",
+];
+
+/// The fields whose source is recorded for each station, see [`MergedStation::provenance`].
+pub const PROVENANCE_FIELDS: [&str; 2] = ["name", "coordinates"];
+
+/// The stations read from a source along with how the source takes precedence over others.
+pub struct SourceStations {
+    /// The name of the source as recorded in the provenance
+    pub name: String,
+    /// The source's priority; when several sources provide a value for a field, the value from
+    /// the source with the highest priority wins
+    pub priority: i64,
+    /// Priorities for particular [`FIELDS`] that override `priority`
+    pub field_priorities: HashMap<String, i64>,
+    pub stations: Vec<StationRecord>,
+}
+
+impl SourceStations {
+    fn priority(&self, field: &str) -> i64 {
+        self.field_priorities
+            .get(field)
+            .copied()
+            .unwrap_or(self.priority)
+    }
+}
+
+/// A station merged from the records of one or more sources.
+#[derive(Debug)]
+pub struct MergedStation {
+    pub station: StationRecord,
+    /// The names of the sources the station was found in, in order of priority
+    pub sources: Vec<String>,
+    /// The source each of the [`PROVENANCE_FIELDS`] the station has a value for was taken from
+    pub provenance: BTreeMap<&'static str, String>,
+}
+
+/// What identifies a station across sources.
+#[derive(Hash, PartialEq, Eq)]
+enum Key {
+    Uic(String),
+    AlternativeId(String, String),
+}
+
+/// Returns the station's UIC and its IDs in carriers' schemes, see [`CARRIER_ID_COLUMNS`].
+///
+/// Other alternative IDs, e.g. GTFS stop IDs or NeTEx private codes, are only unique within their
+/// source and must not join stations of different sources.
+fn keys(station: &StationRecord) -> Vec<Key> {
+    let uic = (!station.uic.is_empty()).then(|| Key::Uic(station.uic.clone()));
+    uic.into_iter()
+        .chain(
+            station
+                .alternative_ids
+                .iter()
+                .filter(|(scheme, _)| {
+                    CARRIER_ID_COLUMNS
+                        .iter()
+                        .any(|(_, carrier_scheme)| carrier_scheme == scheme)
+                })
+                .map(|(scheme, value)| Key::AlternativeId(scheme.clone(), value.clone())),
+        )
+        .collect()
+}
+
+/// Merges the stations read from several sources.
+///
+/// Records from different sources are matched if they have the same UIC or the same ID in a
+/// carrier's scheme (see [`CARRIER_ID_COLUMNS`]), also transitively, e.g. when a record shares its
+/// UIC with one record and a carrier ID with another. Other alternative IDs are not matched.
+/// Records from the same source are never merged; a record that would join two records of the
+/// same source only joins the one from the first matching station.
+///
+/// For each of the [`FIELDS`], the value is taken from the matched record with the highest
+/// priority for that field that has a value; alternative IDs are combined from all records.
+///
+/// A merged station keeps the ID of the record from the source with the highest priority, and
+/// references to other stations, e.g. `parent_station_id`, are translated to the merged stations'
+/// IDs.
+pub fn merge(sources: &[SourceStations]) -> Vec<MergedStation> {
+    let mut order: Vec<usize> = (0..sources.len()).collect();
+    order.sort_by_key(|source| -sources[*source].priority);
+    let mut ranks = vec![0; sources.len()];
+    for (rank, source) in order.iter().enumerate() {
+        ranks[*source] = rank;
+    }
+
+    // each group is the list of (source, station) indices that make up one merged station; groups
+    // that a record matches through different keys are merged into the one created first, whose
+    // index the later ones then point to in `parents`
+    let mut groups: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut parents: Vec<usize> = Vec::new();
+    let mut groups_by_key: HashMap<Key, usize> = HashMap::new();
+    let mut groups_by_station_id: HashMap<(usize, i64), usize> = HashMap::new();
+    for source in order {
+        for (index, station) in sources[source].stations.iter().enumerate() {
+            let keys = keys(station);
+            let mut matched: Vec<usize> = keys
+                .iter()
+                .filter_map(|key| groups_by_key.get(key))
+                .map(|group| root(&parents, *group))
+                .collect();
+            matched.sort_unstable();
+            matched.dedup();
+
+            let mut group = None;
+            for candidate in matched {
+                if groups[candidate]
+                    .iter()
+                    .any(|(member_source, _)| *member_source == source)
+                {
+                    continue;
+                }
+                match group {
+                    None => group = Some(candidate),
+                    Some(group) => {
+                        if shares_source(&groups[group], &groups[candidate]) {
+                            continue;
+                        }
+                        let members = std::mem::take(&mut groups[candidate]);
+                        groups[group].extend(members);
+                        groups[group].sort_by_key(|(source, index)| (ranks[*source], *index));
+                        parents[candidate] = group;
+                    }
+                }
+            }
+            let group = group.unwrap_or_else(|| {
+                groups.push(Vec::new());
+                parents.push(groups.len() - 1);
+                groups.len() - 1
+            });
+            groups[group].push((source, index));
+            for key in keys {
+                groups_by_key.entry(key).or_insert(group);
+            }
+            groups_by_station_id.insert((source, station.id), group);
+        }
+    }
+    let mut positions = vec![0; groups.len()];
+    let groups: Vec<Vec<(usize, usize)>> = groups
+        .into_iter()
+        .enumerate()
+        .filter(|(group, _)| parents[*group] == *group)
+        .enumerate()
+        .map(|(position, (group, members))| {
+            positions[group] = position;
+            members
+        })
+        .collect();
+    let group_position = |group: usize| positions[root(&parents, group)];
+
+    let mut used_ids = HashSet::new();
+    let group_ids: Vec<i64> = groups
+        .iter()
+        .map(|members| {
+            let (source, index) = members[0];
+            let id = sources[source].stations[index].id;
+            // stations from different sources may have the same ID without being the same station
+            if used_ids.insert(id) {
+                id
+            } else {
+                let id = hashed_station_id(&format!("{}:{}", sources[source].name, id));
+                used_ids.insert(id);
+                id
+            }
+        })
+        .collect();
+    let translate = |source: usize, id: Option<i64>| {
+        id.map(|id| {
+            groups_by_station_id
+                .get(&(source, id))
+                .map(|group| group_ids[group_position(*group)])
+                .unwrap_or(id)
+        })
+    };
+
+    groups
+        .iter()
+        .zip(&group_ids)
+        .map(|(members, id)| {
+            let (first_source, first_index) = members[0];
+            let mut station = sources[first_source].stations[first_index].clone();
+            station.id = *id;
+            station.alternative_ids = Vec::new();
+            let mut provenance = BTreeMap::new();
+
+            for field in FIELDS {
+                let mut chosen: Option<(usize, &StationRecord)> = None;
+                for (source, index) in members {
+                    let candidate = &sources[*source].stations[*index];
+                    let wins = match chosen {
+                        None => true,
+                        Some((chosen_source, _)) => {
+                            sources[*source].priority(field)
+                                > sources[chosen_source].priority(field)
+                        }
+                    };
+                    if has_value(candidate, field) && wins {
+                        chosen = Some((*source, candidate));
+                    }
+                }
+                let Some((source, record)) = chosen else {
+                    continue;
+                };
+                take(&mut station, record, field);
+                if field == "parent_station" {
+                    station.parent_station_id = translate(source, record.parent_station_id);
+                } else if field == "same_as" {
+                    station.same_as = translate(source, record.same_as);
+                }
+                if let Some(field) = PROVENANCE_FIELDS.iter().find(|f| **f == field) {
+                    provenance.insert(*field, sources[source].name.clone());
+                }
+            }
+
+            for (source, index) in members {
+                for (scheme, value) in &sources[*source].stations[*index].alternative_ids {
+                    if !station
+                        .alternative_ids
+                        .iter()
+                        .any(|(existing, _)| existing == scheme)
+                    {
+                        station
+                            .alternative_ids
+                            .push((scheme.clone(), value.clone()));
+                    }
+                }
+            }
+
+            MergedStation {
+                station,
+                sources: members
+                    .iter()
+                    .map(|(source, _)| sources[*source].name.clone())
+                    .collect(),
+                provenance,
+            }
+        })
+        .collect()
+}
+
+/// The group the passed group has been merged into.
+fn root(parents: &[usize], mut group: usize) -> usize {
+    while parents[group] != group {
+        group = parents[group];
+    }
+    group
+}
+
+fn shares_source(group: &[(usize, usize)], other: &[(usize, usize)]) -> bool {
+    group
+        .iter()
+        .any(|(source, _)| other.iter().any(|(other, _)| other == source))
+}
+
+fn has_value(station: &StationRecord, field: &str) -> bool {
+    match field {
+        "name" => !station.name.is_empty(),
+        "slug" => station.slug.is_some(),
+        "uic" => !station.uic.is_empty(),
+        "uic8_sncf" => station.uic8_sncf.is_some(),
+        "coordinates" => station.latitude.is_some() && station.longitude.is_some(),
+        "parent_station" => station.parent_station_id.is_some(),
+        "country" => station.country.is_some(),
+        "time_zone" => station.time_zone.is_some(),
+        "flags" => true,
+        "same_as" => station.same_as.is_some(),
+        "info" => [
+            &station.info_de,
+            &station.info_en,
+            &station.info_es,
+            &station.info_fr,
+            &station.info_it,
+            &station.info_nb,
+            &station.info_nl,
+            &station.info_cs,
+            &station.info_da,
+            &station.info_hu,
+            &station.info_ja,
+            &station.info_ko,
+            &station.info_pl,
+            &station.info_pt,
+            &station.info_ru,
+            &station.info_sv,
+            &station.info_tr,
+            &station.info_zh,
+        ]
+        .iter()
+        .any(|info| info.is_some()),
+        _ => false,
+    }
+}
+
+/// Copies the field's value from the source record to the station.
+fn take(station: &mut StationRecord, record: &StationRecord, field: &str) {
+    match field {
+        "name" => station.name = record.name.clone(),
+        "slug" => station.slug = record.slug.clone(),
+        "uic" => station.uic = record.uic.clone(),
+        "uic8_sncf" => station.uic8_sncf = record.uic8_sncf.clone(),
+        "coordinates" => {
+            station.latitude = record.latitude;
+            station.longitude = record.longitude;
+        }
+        "country" => station.country = record.country.clone(),
+        "time_zone" => station.time_zone = record.time_zone.clone(),
+        "flags" => {
+            station.is_city = record.is_city;
+            station.is_main_station = record.is_main_station;
+            station.is_airport = record.is_airport;
+            station.is_suggestable = record.is_suggestable;
+            station.country_hint = record.country_hint;
+            station.main_station_hint = record.main_station_hint;
+        }
+        "info" => {
+            station.info_de = record.info_de.clone();
+            station.info_en = record.info_en.clone();
+            station.info_es = record.info_es.clone();
+            station.info_fr = record.info_fr.clone();
+            station.info_it = record.info_it.clone();
+            station.info_nb = record.info_nb.clone();
+            station.info_nl = record.info_nl.clone();
+            station.info_cs = record.info_cs.clone();
+            station.info_da = record.info_da.clone();
+            station.info_hu = record.info_hu.clone();
+            station.info_ja = record.info_ja.clone();
+            station.info_ko = record.info_ko.clone();
+            station.info_pl = record.info_pl.clone();
+            station.info_pt = record.info_pt.clone();
+            station.info_ru = record.info_ru.clone();
+            station.info_sv = record.info_sv.clone();
+            station.info_tr = record.info_tr.clone();
+            station.info_zh = record.info_zh.clone();
+        }
+        // references to other stations are translated by the caller
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station(id: i64, name: &str, uic: &str, alternative_ids: &[(&str, &str)]) -> StationRecord {
+        StationRecord {
+            id,
+            name: name.to_string(),
+            uic: uic.to_string(),
+            alternative_ids: alternative_ids
+                .iter()
+                .map(|(scheme, value)| (scheme.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn source(
+        name: &str,
+        priority: i64,
+        field_priorities: &[(&str, i64)],
+        stations: Vec<StationRecord>,
+    ) -> SourceStations {
+        SourceStations {
+            name: name.to_string(),
+            priority,
+            field_priorities: field_priorities
+                .iter()
+                .map(|(field, priority)| (field.to_string(), *priority))
+                .collect(),
+            stations,
+        }
+    }
+
+    #[test]
+    fn test_merge_by_uic_and_carrier_id() {
+        let mut paris_est = station(4916, "Paris Gare de l'Est", "8711300", &[("sncf", "FRPST")]);
+        paris_est.latitude = Some(48.876742);
+        paris_est.longitude = Some(2.358424);
+        paris_est.parent_station_id = Some(4924);
+        let paris = station(4924, "Paris", "8796001", &[]);
+        let mut gtfs_paris_est = station(1, "Paris Est", "", &[("gtfs", "P1"), ("sncf", "FRPST")]);
+        gtfs_paris_est.latitude = Some(48.8768);
+        gtfs_paris_est.longitude = Some(2.3592);
+        let mut netex_paris = station(2, "Paris (all stations)", "8796001", &[("netex", "FR:1")]);
+        netex_paris.info_en = Some(String::from("Paris"));
+        let gtfs_only = station(3, "Market Square", "", &[("gtfs", "S2")]);
+
+        let merged = merge(&[
+            source(
+                "gtfs",
+                10,
+                &[("coordinates", 200)],
+                vec![gtfs_paris_est, gtfs_only],
+            ),
+            source("trainline", 100, &[], vec![paris_est, paris]),
+            source("netex", 50, &[], vec![netex_paris]),
+        ]);
+
+        assert_eq!(merged.len(), 3);
+
+        let paris_est = &merged[0];
+        assert_eq!(paris_est.station.id, 4916);
+        assert_eq!(paris_est.station.name, "Paris Gare de l'Est");
+        assert_eq!(paris_est.station.uic, "8711300");
+        assert_eq!(paris_est.station.latitude, Some(48.8768));
+        assert_eq!(paris_est.station.longitude, Some(2.3592));
+        assert_eq!(paris_est.station.parent_station_id, Some(4924));
+        assert_eq!(
+            paris_est.station.alternative_ids,
+            vec![
+                (String::from("sncf"), String::from("FRPST")),
+                (String::from("gtfs"), String::from("P1")),
+            ]
+        );
+        assert_eq!(paris_est.sources, vec!["trainline", "gtfs"]);
+        assert_eq!(paris_est.provenance["name"], "trainline");
+        assert_eq!(paris_est.provenance["coordinates"], "gtfs");
+
+        let paris = &merged[1];
+        assert_eq!(paris.station.id, 4924);
+        assert_eq!(paris.station.name, "Paris");
+        assert_eq!(paris.station.info_en.as_deref(), Some("Paris"));
+        assert_eq!(paris.sources, vec!["trainline", "netex"]);
+        assert!(!paris.provenance.contains_key("coordinates"));
+
+        let market_square = &merged[2];
+        assert_eq!(market_square.station.id, 3);
+        assert_eq!(market_square.sources, vec!["gtfs"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_source_local_ids_apart() {
+        let merged = merge(&[
+            source(
+                "regional",
+                50,
+                &[],
+                vec![station(
+                    1,
+                    "Central Station",
+                    "",
+                    &[("gtfs", "1"), ("private_code", "3")],
+                )],
+            ),
+            source(
+                "city",
+                10,
+                &[],
+                vec![station(
+                    2,
+                    "Market Square",
+                    "",
+                    &[("gtfs", "1"), ("private_code", "3")],
+                )],
+            ),
+        ]);
+
+        // stop IDs and private codes are only unique within a source
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].sources, vec!["regional"]);
+        assert_eq!(merged[1].sources, vec!["city"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_stations_of_one_source_apart() {
+        let merged = merge(&[source(
+            "trainline",
+            0,
+            &[],
+            vec![
+                station(1, "Paris", "8796001", &[]),
+                station(2, "Paris (city)", "8796001", &[]),
+            ],
+        )]);
+
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_records_matching_several_stations() {
+        let merged = merge(&[
+            source(
+                "trainline",
+                100,
+                &[],
+                vec![station(4916, "Paris Gare de l'Est", "8711300", &[])],
+            ),
+            source(
+                "netex",
+                50,
+                &[],
+                vec![station(
+                    1,
+                    "Paris Est",
+                    "",
+                    &[("netex", "FR:1"), ("sncf", "FRPST")],
+                )],
+            ),
+            source(
+                "gtfs",
+                10,
+                &[],
+                vec![station(2, "Paris-Est", "8711300", &[("sncf", "FRPST")])],
+            ),
+        ]);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].station.id, 4916);
+        assert_eq!(merged[0].sources, vec!["trainline", "netex", "gtfs"]);
+        assert_eq!(
+            merged[0].station.alternative_ids,
+            vec![
+                (String::from("netex"), String::from("FR:1")),
+                (String::from("sncf"), String::from("FRPST")),
+            ]
+        );
+
+        // two stations of the same source are never merged, even through a third record
+        let merged = merge(&[
+            source(
+                "trainline",
+                100,
+                &[],
+                vec![
+                    station(4916, "Paris Gare de l'Est", "8711300", &[]),
+                    station(4917, "Paris Est (bus)", "", &[("sncf", "FRPST")]),
+                ],
+            ),
+            source(
+                "gtfs",
+                10,
+                &[],
+                vec![station(2, "Paris-Est", "8711300", &[("sncf", "FRPST")])],
+            ),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].sources, vec!["trainline", "gtfs"]);
+        assert_eq!(merged[1].sources, vec!["trainline"]);
+    }
+
+    #[test]
+    fn test_merge_translates_references_and_conflicting_ids() {
+        let mut platform = station(2, "Platform 1", "", &[("gtfs", "P1")]);
+        platform.parent_station_id = Some(1);
+
+        let merged = merge(&[
+            source(
+                "trainline",
+                100,
+                &[],
+                vec![station(1, "Paris", "8796001", &[])],
+            ),
+            source(
+                "gtfs",
+                10,
+                &[],
+                vec![station(1, "Central Station", "8796001", &[]), platform],
+            ),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        // the platform keeps its ID and points at the station its parent was merged into
+        assert_eq!(merged[1].station.id, 2);
+        assert_eq!(merged[1].station.parent_station_id, Some(1));
+
+        let merged = merge(&[
+            source(
+                "trainline",
+                100,
+                &[],
+                vec![station(1, "Paris", "8796001", &[])],
+            ),
+            source("gtfs", 10, &[], vec![station(1, "Lyon", "8772319", &[])]),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].station.id, 1);
+        assert_eq!(merged[1].station.id, hashed_station_id("gtfs:1"));
+    }
+}
@@ -0,0 +1,782 @@
+use crate::importers::StationRecord;
+use crate::merge::MergedStation;
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// The source recorded in a station's provenance for the fields overrides set.
+pub const OVERRIDES_SOURCE: &str = "overrides";
+
+/// Local corrections that are applied on top of the source data when synchronizing.
+///
+/// Overrides are read from a TOML file:
+///
+/// ```toml
+/// # fix a station's data until the fix has landed upstream
+/// [[patch]]
+/// id = 8267
+/// name = "Lisboa Santa Apolónia"
+/// latitude = 38.71387
+/// unset = ["time_zone"]
+///
+/// # add a station that is missing upstream
+/// [[add]]
+/// id = 1000001
+/// name = "Example Station"
+/// uic = "8799999"
+///
+/// # remove stations from the database
+/// hide = [4916]
+/// ```
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Overrides {
+    /// Changes to stations in the source data
+    #[serde(default)]
+    pub patch: Vec<StationPatch>,
+    /// Stations that are missing in the source data; `name` and `uic` are required and stations
+    /// are suggestable unless `is_suggestable = false` is set
+    #[serde(default)]
+    pub add: Vec<StationPatch>,
+    /// The IDs of stations in the source data that are removed from the database; references to
+    /// them, e.g. other stations' `parent_station_id`, are removed as well
+    #[serde(default)]
+    pub hide: Vec<i64>,
+}
+
+/// The fields of stations that can be unset, i.e. set to `NULL`, via [`StationPatch::unset`].
+pub const NULLABLE_FIELDS: [&str; 26] = [
+    "slug",
+    "uic8_sncf",
+    "latitude",
+    "longitude",
+    "parent_station_id",
+    "country",
+    "time_zone",
+    "same_as",
+    "info_de",
+    "info_en",
+    "info_es",
+    "info_fr",
+    "info_it",
+    "info_nb",
+    "info_nl",
+    "info_cs",
+    "info_da",
+    "info_hu",
+    "info_ja",
+    "info_ko",
+    "info_pl",
+    "info_pt",
+    "info_ru",
+    "info_sv",
+    "info_tr",
+    "info_zh",
+];
+
+/// The fields to set on a station, identified by its ID; fields that are not set are left as is.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StationPatch {
+    pub id: i64,
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub uic: Option<String>,
+    pub uic8_sncf: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub parent_station_id: Option<i64>,
+    pub country: Option<String>,
+    pub time_zone: Option<String>,
+    pub is_city: Option<bool>,
+    pub is_main_station: Option<bool>,
+    pub is_airport: Option<bool>,
+    pub is_suggestable: Option<bool>,
+    pub country_hint: Option<bool>,
+    pub main_station_hint: Option<bool>,
+    pub same_as: Option<i64>,
+    pub info_de: Option<String>,
+    pub info_en: Option<String>,
+    pub info_es: Option<String>,
+    pub info_fr: Option<String>,
+    pub info_it: Option<String>,
+    pub info_nb: Option<String>,
+    pub info_nl: Option<String>,
+    pub info_cs: Option<String>,
+    pub info_da: Option<String>,
+    pub info_hu: Option<String>,
+    pub info_ja: Option<String>,
+    pub info_ko: Option<String>,
+    pub info_pl: Option<String>,
+    pub info_pt: Option<String>,
+    pub info_ru: Option<String>,
+    pub info_sv: Option<String>,
+    pub info_tr: Option<String>,
+    pub info_zh: Option<String>,
+    /// The fields to unset, which must be in [`NULLABLE_FIELDS`] and not be set by the patch
+    #[serde(default)]
+    pub unset: Vec<String>,
+}
+
+impl StationPatch {
+    fn unsets(&self, field: &str) -> bool {
+        self.unset.iter().any(|unset| unset == field)
+    }
+
+    /// The names of the fields the patch would change on the passed station.
+    pub fn changed_fields(&self, station: &StationRecord) -> Vec<&'static str> {
+        fn differs<T: PartialEq>(patch: &Option<T>, current: &T) -> bool {
+            patch.as_ref().is_some_and(|value| value != current)
+        }
+        fn differs_optional<T: PartialEq>(
+            patch: &Option<T>,
+            unset: bool,
+            current: &Option<T>,
+        ) -> bool {
+            (patch.is_some() && patch != current) || (unset && current.is_some())
+        }
+
+        [
+            ("name", differs(&self.name, &station.name)),
+            (
+                "slug",
+                differs_optional(&self.slug, self.unsets("slug"), &station.slug),
+            ),
+            ("uic", differs(&self.uic, &station.uic)),
+            (
+                "uic8_sncf",
+                differs_optional(
+                    &self.uic8_sncf,
+                    self.unsets("uic8_sncf"),
+                    &station.uic8_sncf,
+                ),
+            ),
+            (
+                "latitude",
+                differs_optional(&self.latitude, self.unsets("latitude"), &station.latitude),
+            ),
+            (
+                "longitude",
+                differs_optional(
+                    &self.longitude,
+                    self.unsets("longitude"),
+                    &station.longitude,
+                ),
+            ),
+            (
+                "parent_station_id",
+                differs_optional(
+                    &self.parent_station_id,
+                    self.unsets("parent_station_id"),
+                    &station.parent_station_id,
+                ),
+            ),
+            (
+                "country",
+                differs_optional(&self.country, self.unsets("country"), &station.country),
+            ),
+            (
+                "time_zone",
+                differs_optional(
+                    &self.time_zone,
+                    self.unsets("time_zone"),
+                    &station.time_zone,
+                ),
+            ),
+            ("is_city", differs(&self.is_city, &station.is_city)),
+            (
+                "is_main_station",
+                differs(&self.is_main_station, &station.is_main_station),
+            ),
+            ("is_airport", differs(&self.is_airport, &station.is_airport)),
+            (
+                "is_suggestable",
+                differs(&self.is_suggestable, &station.is_suggestable),
+            ),
+            (
+                "country_hint",
+                differs(&self.country_hint, &station.country_hint),
+            ),
+            (
+                "main_station_hint",
+                differs(&self.main_station_hint, &station.main_station_hint),
+            ),
+            (
+                "same_as",
+                differs_optional(&self.same_as, self.unsets("same_as"), &station.same_as),
+            ),
+            (
+                "info_de",
+                differs_optional(&self.info_de, self.unsets("info_de"), &station.info_de),
+            ),
+            (
+                "info_en",
+                differs_optional(&self.info_en, self.unsets("info_en"), &station.info_en),
+            ),
+            (
+                "info_es",
+                differs_optional(&self.info_es, self.unsets("info_es"), &station.info_es),
+            ),
+            (
+                "info_fr",
+                differs_optional(&self.info_fr, self.unsets("info_fr"), &station.info_fr),
+            ),
+            (
+                "info_it",
+                differs_optional(&self.info_it, self.unsets("info_it"), &station.info_it),
+            ),
+            (
+                "info_nb",
+                differs_optional(&self.info_nb, self.unsets("info_nb"), &station.info_nb),
+            ),
+            (
+                "info_nl",
+                differs_optional(&self.info_nl, self.unsets("info_nl"), &station.info_nl),
+            ),
+            (
+                "info_cs",
+                differs_optional(&self.info_cs, self.unsets("info_cs"), &station.info_cs),
+            ),
+            (
+                "info_da",
+                differs_optional(&self.info_da, self.unsets("info_da"), &station.info_da),
+            ),
+            (
+                "info_hu",
+                differs_optional(&self.info_hu, self.unsets("info_hu"), &station.info_hu),
+            ),
+            (
+                "info_ja",
+                differs_optional(&self.info_ja, self.unsets("info_ja"), &station.info_ja),
+            ),
+            (
+                "info_ko",
+                differs_optional(&self.info_ko, self.unsets("info_ko"), &station.info_ko),
+            ),
+            (
+                "info_pl",
+                differs_optional(&self.info_pl, self.unsets("info_pl"), &station.info_pl),
+            ),
+            (
+                "info_pt",
+                differs_optional(&self.info_pt, self.unsets("info_pt"), &station.info_pt),
+            ),
+            (
+                "info_ru",
+                differs_optional(&self.info_ru, self.unsets("info_ru"), &station.info_ru),
+            ),
+            (
+                "info_sv",
+                differs_optional(&self.info_sv, self.unsets("info_sv"), &station.info_sv),
+            ),
+            (
+                "info_tr",
+                differs_optional(&self.info_tr, self.unsets("info_tr"), &station.info_tr),
+            ),
+            (
+                "info_zh",
+                differs_optional(&self.info_zh, self.unsets("info_zh"), &station.info_zh),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(field, _)| field)
+        .collect()
+    }
+
+    /// Sets the fields the patch has a value for on the station, unsets the fields in
+    /// [`StationPatch::unset`] and records the overrides as the source of its name and coordinates
+    /// if the patch changes them; a station without coordinates has no source for them.
+    fn apply_to(&self, merged: &mut MergedStation) {
+        fn set<T: Clone>(patch: &Option<T>, field: &mut T) {
+            if let Some(value) = patch {
+                *field = value.clone();
+            }
+        }
+        fn set_optional<T: Clone>(patch: &Option<T>, unset: bool, field: &mut Option<T>) {
+            if unset {
+                *field = None;
+            } else if patch.is_some() {
+                field.clone_from(patch);
+            }
+        }
+
+        let station = &mut merged.station;
+        set(&self.name, &mut station.name);
+        set_optional(&self.slug, self.unsets("slug"), &mut station.slug);
+        set(&self.uic, &mut station.uic);
+        set_optional(
+            &self.uic8_sncf,
+            self.unsets("uic8_sncf"),
+            &mut station.uic8_sncf,
+        );
+        set_optional(
+            &self.latitude,
+            self.unsets("latitude"),
+            &mut station.latitude,
+        );
+        set_optional(
+            &self.longitude,
+            self.unsets("longitude"),
+            &mut station.longitude,
+        );
+        set_optional(
+            &self.parent_station_id,
+            self.unsets("parent_station_id"),
+            &mut station.parent_station_id,
+        );
+        set_optional(&self.country, self.unsets("country"), &mut station.country);
+        set_optional(
+            &self.time_zone,
+            self.unsets("time_zone"),
+            &mut station.time_zone,
+        );
+        set(&self.is_city, &mut station.is_city);
+        set(&self.is_main_station, &mut station.is_main_station);
+        set(&self.is_airport, &mut station.is_airport);
+        set(&self.is_suggestable, &mut station.is_suggestable);
+        set(&self.country_hint, &mut station.country_hint);
+        set(&self.main_station_hint, &mut station.main_station_hint);
+        set_optional(&self.same_as, self.unsets("same_as"), &mut station.same_as);
+        set_optional(&self.info_de, self.unsets("info_de"), &mut station.info_de);
+        set_optional(&self.info_en, self.unsets("info_en"), &mut station.info_en);
+        set_optional(&self.info_es, self.unsets("info_es"), &mut station.info_es);
+        set_optional(&self.info_fr, self.unsets("info_fr"), &mut station.info_fr);
+        set_optional(&self.info_it, self.unsets("info_it"), &mut station.info_it);
+        set_optional(&self.info_nb, self.unsets("info_nb"), &mut station.info_nb);
+        set_optional(&self.info_nl, self.unsets("info_nl"), &mut station.info_nl);
+        set_optional(&self.info_cs, self.unsets("info_cs"), &mut station.info_cs);
+        set_optional(&self.info_da, self.unsets("info_da"), &mut station.info_da);
+        set_optional(&self.info_hu, self.unsets("info_hu"), &mut station.info_hu);
+        set_optional(&self.info_ja, self.unsets("info_ja"), &mut station.info_ja);
+        set_optional(&self.info_ko, self.unsets("info_ko"), &mut station.info_ko);
+        set_optional(&self.info_pl, self.unsets("info_pl"), &mut station.info_pl);
+        set_optional(&self.info_pt, self.unsets("info_pt"), &mut station.info_pt);
+        set_optional(&self.info_ru, self.unsets("info_ru"), &mut station.info_ru);
+        set_optional(&self.info_sv, self.unsets("info_sv"), &mut station.info_sv);
+        set_optional(&self.info_tr, self.unsets("info_tr"), &mut station.info_tr);
+        set_optional(&self.info_zh, self.unsets("info_zh"), &mut station.info_zh);
+
+        if self.name.is_some() {
+            merged
+                .provenance
+                .insert("name", OVERRIDES_SOURCE.to_string());
+        }
+        let sets_coordinates = self.latitude.is_some() || self.longitude.is_some();
+        let unsets_coordinates = self.unsets("latitude") || self.unsets("longitude");
+        if sets_coordinates || unsets_coordinates {
+            if merged.station.latitude.is_none() && merged.station.longitude.is_none() {
+                merged.provenance.remove("coordinates");
+            } else {
+                merged
+                    .provenance
+                    .insert("coordinates", OVERRIDES_SOURCE.to_string());
+            }
+        }
+    }
+}
+
+/// An override that had no effect, usually because upstream has caught up with it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleOverride {
+    /// A patch whose values the station already has
+    PatchUpToDate(i64),
+    /// A patch for a station that is not in the source data
+    PatchMissingStation(i64),
+    /// An added station that is in the source data now; the override is still applied
+    AddedUpstream(i64),
+    /// A hidden station that is not in the source data
+    HideMissingStation(i64),
+}
+
+impl Display for StaleOverride {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            StaleOverride::PatchUpToDate(id) => {
+                write!(
+                    f,
+                    "patch for station {}: station already has these values",
+                    id
+                )
+            }
+            StaleOverride::PatchMissingStation(id) => {
+                write!(f, "patch for station {}: station does not exist", id)
+            }
+            StaleOverride::AddedUpstream(id) => {
+                write!(f, "added station {}: station exists in source data", id)
+            }
+            StaleOverride::HideMissingStation(id) => {
+                write!(f, "hidden station {}: station does not exist", id)
+            }
+        }
+    }
+}
+
+/// The outcome of applying [`Overrides`].
+#[derive(Debug, Default, PartialEq)]
+pub struct OverridesReport {
+    pub patched: usize,
+    pub added: usize,
+    pub hidden: usize,
+    pub stale: Vec<StaleOverride>,
+}
+
+/// Reads and validates the overrides from the passed TOML file.
+pub async fn load(path: &Path) -> Result<Overrides, anyhow::Error> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .context(format!("Failed to read overrides from {}!", path.display()))?;
+    let overrides: Overrides = toml::from_str(&contents)
+        .context(format!("Failed to parse overrides in {}!", path.display()))?;
+    validate(&overrides).context(format!("Invalid overrides in {}!", path.display()))?;
+    Ok(overrides)
+}
+
+fn validate(overrides: &Overrides) -> Result<(), anyhow::Error> {
+    let mut ids = HashSet::new();
+    let all_ids = overrides
+        .patch
+        .iter()
+        .chain(&overrides.add)
+        .map(|patch| patch.id)
+        .chain(overrides.hide.iter().copied());
+    for id in all_ids {
+        if !ids.insert(id) {
+            return Err(anyhow!("Station {} is overridden more than once!", id));
+        }
+    }
+
+    for patch in overrides.patch.iter().chain(&overrides.add) {
+        // the nullable fields are all unset on a default record, so the patch changes exactly
+        // those of them it sets
+        let set_fields = patch.changed_fields(&StationRecord::default());
+        for field in &patch.unset {
+            if !NULLABLE_FIELDS.contains(&field.as_str()) {
+                return Err(anyhow!(
+                    "Station {} cannot unset {}, only {} can be unset!",
+                    patch.id,
+                    field,
+                    NULLABLE_FIELDS.join(", ")
+                ));
+            }
+            if set_fields.contains(&field.as_str()) {
+                return Err(anyhow!(
+                    "Station {} both sets and unsets {}!",
+                    patch.id,
+                    field
+                ));
+            }
+        }
+    }
+
+    for station in &overrides.add {
+        if station.name.is_none() || station.uic.is_none() {
+            return Err(anyhow!(
+                "Added station {} must have a name and a UIC!",
+                station.id
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the overrides to the stations merged from the sources.
+///
+/// The overrides are applied before the stations are written to the database so that the
+/// synchronization's report and checks reflect the data that is actually written. Overrides that
+/// had no effect are reported in [`OverridesReport::stale`] so they can be removed once upstream
+/// has caught up.
+pub fn apply(stations: &mut Vec<MergedStation>, overrides: &Overrides) -> OverridesReport {
+    let mut report = OverridesReport::default();
+    let positions: HashMap<i64, usize> = stations
+        .iter()
+        .enumerate()
+        .map(|(position, merged)| (merged.station.id, position))
+        .collect();
+
+    for patch in &overrides.patch {
+        match positions
+            .get(&patch.id)
+            .map(|position| &mut stations[*position])
+        {
+            Some(merged) if patch.changed_fields(&merged.station).is_empty() => {
+                report.stale.push(StaleOverride::PatchUpToDate(patch.id));
+            }
+            Some(merged) => {
+                patch.apply_to(merged);
+                report.patched += 1;
+            }
+            None => {
+                report
+                    .stale
+                    .push(StaleOverride::PatchMissingStation(patch.id));
+            }
+        }
+    }
+
+    for station in &overrides.add {
+        match positions.get(&station.id) {
+            Some(position) => {
+                station.apply_to(&mut stations[*position]);
+                report.stale.push(StaleOverride::AddedUpstream(station.id));
+            }
+            None => {
+                let mut merged = MergedStation {
+                    station: StationRecord {
+                        id: station.id,
+                        is_suggestable: true,
+                        ..Default::default()
+                    },
+                    sources: vec![OVERRIDES_SOURCE.to_string()],
+                    provenance: BTreeMap::new(),
+                };
+                station.apply_to(&mut merged);
+                stations.push(merged);
+                report.added += 1;
+            }
+        }
+    }
+
+    let hidden: HashSet<i64> = overrides.hide.iter().copied().collect();
+    for id in &overrides.hide {
+        if !positions.contains_key(id) {
+            report.stale.push(StaleOverride::HideMissingStation(*id));
+        }
+    }
+    let before = stations.len();
+    stations.retain(|merged| !hidden.contains(&merged.station.id));
+    report.hidden = before - stations.len();
+    for merged in stations.iter_mut() {
+        let station = &mut merged.station;
+        if station
+            .parent_station_id
+            .is_some_and(|id| hidden.contains(&id))
+        {
+            station.parent_station_id = None;
+        }
+        if station.same_as.is_some_and(|id| hidden.contains(&id)) {
+            station.same_as = None;
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(id: i64, name: &str, uic: &str, coordinates: Option<(f64, f64)>) -> MergedStation {
+        let mut provenance = BTreeMap::from([("name", String::from("trainline"))]);
+        if coordinates.is_some() {
+            provenance.insert("coordinates", String::from("trainline"));
+        }
+        MergedStation {
+            station: StationRecord {
+                id,
+                name: String::from(name),
+                uic: String::from(uic),
+                latitude: coordinates.map(|(latitude, _)| latitude),
+                longitude: coordinates.map(|(_, longitude)| longitude),
+                ..Default::default()
+            },
+            sources: vec![String::from("trainline")],
+            provenance,
+        }
+    }
+
+    fn stations() -> Vec<MergedStation> {
+        vec![
+            merged(
+                1,
+                "Lisboa Santa Apolonia",
+                "8721428",
+                Some((38.71387, -9.122271)),
+            ),
+            merged(2, "Porto Campanhã", "9400300", Some((41.148, -8.585))),
+            merged(3, "Closed Station", "8799998", None),
+            merged(5, "Braga", "9400510", Some((41.548, -8.434))),
+        ]
+    }
+
+    fn with_parent(mut merged: MergedStation, parent_station_id: i64) -> MergedStation {
+        merged.station.parent_station_id = Some(parent_station_id);
+        merged
+    }
+
+    #[test]
+    fn test_validate() {
+        let overrides: Overrides = toml::from_str(
+            r#"
+            hide = [1]
+
+            [[patch]]
+            id = 1
+            name = "Lisboa"
+            "#,
+        )
+        .unwrap();
+        assert!(validate(&overrides).is_err());
+
+        let overrides: Overrides = toml::from_str(
+            r#"
+            [[add]]
+            id = 4
+            name = "No UIC"
+            "#,
+        )
+        .unwrap();
+        assert!(validate(&overrides).is_err());
+
+        assert!(toml::from_str::<Overrides>("[[patch]]\nid = 1\nnmae = \"Typo\"").is_err());
+
+        let overrides: Overrides = toml::from_str(
+            r#"
+            [[patch]]
+            id = 1
+            unset = ["name"]
+            "#,
+        )
+        .unwrap();
+        assert!(validate(&overrides).is_err());
+
+        let overrides: Overrides = toml::from_str(
+            r#"
+            [[patch]]
+            id = 1
+            time_zone = "Europe/Lisbon"
+            unset = ["time_zone"]
+            "#,
+        )
+        .unwrap();
+        assert!(validate(&overrides).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut stations = stations();
+        stations.push(with_parent(merged(6, "Closed Platform", "", None), 3));
+        let overrides: Overrides = toml::from_str(
+            r#"
+            hide = [3, 99]
+
+            [[patch]]
+            id = 1
+            name = "Lisboa Santa Apolónia"
+            info_fr = "Lisbonne Santa Apolónia"
+
+            [[patch]]
+            id = 2
+            name = "Porto Campanhã"
+
+            [[patch]]
+            id = 98
+            name = "Gone"
+
+            [[add]]
+            id = 4
+            name = "Example Station"
+            uic = "8799999"
+            latitude = 48.1
+            longitude = 2.3
+
+            [[add]]
+            id = 5
+            name = "Braga"
+            uic = "9400510"
+            "#,
+        )
+        .unwrap();
+        validate(&overrides).unwrap();
+
+        let report = apply(&mut stations, &overrides);
+
+        assert_eq!(
+            report,
+            OverridesReport {
+                patched: 1,
+                added: 1,
+                hidden: 1,
+                stale: vec![
+                    StaleOverride::PatchUpToDate(2),
+                    StaleOverride::PatchMissingStation(98),
+                    StaleOverride::AddedUpstream(5),
+                    StaleOverride::HideMissingStation(99),
+                ],
+            }
+        );
+
+        let find = |id: i64| stations.iter().find(|merged| merged.station.id == id);
+        let patched = find(1).unwrap();
+        assert_eq!(patched.station.name, "Lisboa Santa Apolónia");
+        assert_eq!(patched.station.uic, "8721428");
+        assert_eq!(patched.station.latitude, Some(38.71387));
+        assert_eq!(
+            patched.station.info_fr.as_deref(),
+            Some("Lisbonne Santa Apolónia")
+        );
+        assert_eq!(patched.provenance["name"], "overrides");
+        assert_eq!(patched.provenance["coordinates"], "trainline");
+
+        let added = find(4).unwrap();
+        assert_eq!(added.station.name, "Example Station");
+        assert!(added.station.is_suggestable);
+        assert!(!added.station.is_city);
+        assert_eq!(added.sources, vec![String::from("overrides")]);
+        assert_eq!(added.provenance["name"], "overrides");
+        assert_eq!(added.provenance["coordinates"], "overrides");
+
+        assert_eq!(find(5).unwrap().provenance["name"], "overrides");
+        assert!(find(3).is_none());
+        // stations don't reference hidden stations
+        assert_eq!(find(6).unwrap().station.parent_station_id, None);
+        assert_eq!(stations.len(), 5);
+    }
+
+    #[test]
+    fn test_apply_unset() {
+        let mut stations = stations();
+        stations[0].station.time_zone = Some(String::from("Europe/Lisbon"));
+        stations[1].station.time_zone = Some(String::from("Europe/Lisbon"));
+        let overrides: Overrides = toml::from_str(
+            r#"
+            [[patch]]
+            id = 1
+            main_station_hint = true
+            unset = ["latitude", "longitude", "time_zone"]
+
+            [[patch]]
+            id = 2
+            latitude = 41.149
+            unset = ["time_zone"]
+
+            [[patch]]
+            id = 3
+            unset = ["slug"]
+            "#,
+        )
+        .unwrap();
+        validate(&overrides).unwrap();
+
+        let report = apply(&mut stations, &overrides);
+
+        assert_eq!(report.patched, 2);
+        assert_eq!(report.stale, vec![StaleOverride::PatchUpToDate(3)]);
+
+        let unset = &stations[0];
+        assert_eq!(unset.station.latitude, None);
+        assert_eq!(unset.station.longitude, None);
+        assert_eq!(unset.station.time_zone, None);
+        assert!(unset.station.main_station_hint);
+        assert!(!unset.provenance.contains_key("coordinates"));
+
+        let moved = &stations[1];
+        assert_eq!(moved.station.latitude, Some(41.149));
+        assert_eq!(moved.station.longitude, Some(-8.585));
+        assert_eq!(moved.station.time_zone, None);
+        assert_eq!(moved.provenance["coordinates"], "overrides");
+    }
+}
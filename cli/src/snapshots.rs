@@ -0,0 +1,262 @@
+use anyhow::{anyhow, Context};
+use restations_db::entities::dataset_metadata::{self, DatasetMetadata};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::ConnectOptions;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// The suffix of snapshot files, which are named `<id>.sqlite.db`.
+const SNAPSHOT_SUFFIX: &str = ".sqlite.db";
+
+/// A copy of the database as it was after a synchronization, see [`create`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// When the snapshot was taken in UTC, e.g. `20250101T120000Z`, followed by a counter like
+    /// `-1` if several snapshots were taken in the same second; snapshots sort by their
+    /// timestamps and then by their counters
+    pub id: String,
+    pub path: PathBuf,
+}
+
+/// What a snapshot contains.
+#[derive(Debug)]
+pub struct SnapshotContents {
+    /// The number of stations in the snapshot
+    pub stations: i64,
+    /// The datasets the snapshot was synchronized from, highest priority first
+    pub metadata: Vec<DatasetMetadata>,
+}
+
+impl Snapshot {
+    /// Opens the snapshot read-only.
+    pub async fn connect(&self) -> Result<SqliteConnection, anyhow::Error> {
+        SqliteConnectOptions::new()
+            .filename(&self.path)
+            .read_only(true)
+            .connect()
+            .await
+            .context(format!("Failed to open snapshot {}!", self.id))
+    }
+
+    /// Loads the number of stations and the dataset metadata stored in the snapshot.
+    pub async fn contents(&self) -> Result<SnapshotContents, anyhow::Error> {
+        let mut conn = self.connect().await?;
+        let stations = sqlx::query_scalar("SELECT COUNT(*) FROM stations")
+            .fetch_one(&mut conn)
+            .await
+            .context(format!("Failed to read snapshot {}!", self.id))?;
+        let metadata = load_metadata(&mut conn).await?;
+        Ok(SnapshotContents { stations, metadata })
+    }
+}
+
+impl Snapshot {
+    /// The timestamp and counter the snapshot sorts by, so that e.g. `-10` sorts after `-2`.
+    fn sort_key(&self) -> (&str, u32) {
+        match self.id.split_once('-') {
+            Some((timestamp, counter)) => (timestamp, counter.parse().unwrap_or(0)),
+            None => (&self.id, 0),
+        }
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Loads the metadata of the datasets the database was synchronized from, which is empty if it
+/// has never been synchronized.
+pub async fn load_metadata(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<DatasetMetadata>, anyhow::Error> {
+    match dataset_metadata::load_all(conn).await {
+        Ok(metadata) => Ok(metadata),
+        Err(restations_db::Error::NoRecordFound) => Ok(Vec::new()),
+        Err(e) => Err(anyhow!(e).context("Failed to load dataset metadata!")),
+    }
+}
+
+/// Lists the snapshots in the directory, oldest first.
+///
+/// Returns an empty list if the directory does not exist yet.
+pub async fn list(dir: &Path) -> Result<Vec<Snapshot>, anyhow::Error> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!(e).context(format!("Failed to read {}!", dir.display()))),
+    };
+
+    let mut snapshots = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        // temporary files of snapshots that are being written start with a dot
+        if file_name.starts_with('.') {
+            continue;
+        }
+        if let Some(id) = file_name.strip_suffix(SNAPSHOT_SUFFIX) {
+            snapshots.push(Snapshot {
+                id: id.to_string(),
+                path: entry.path(),
+            });
+        }
+    }
+    snapshots.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+
+    Ok(snapshots)
+}
+
+/// Finds the snapshot with the passed ID in the directory.
+pub async fn find(dir: &Path, id: &str) -> Result<Snapshot, anyhow::Error> {
+    list(dir)
+        .await?
+        .into_iter()
+        .find(|snapshot| snapshot.id == id)
+        .ok_or_else(|| {
+            anyhow!(
+                "No snapshot {} in {} – run `cargo db snapshots list` to see all snapshots!",
+                id,
+                dir.display()
+            )
+        })
+}
+
+/// Copies the database file into the directory as a new snapshot.
+///
+/// The copy is written to a temporary file first and only renamed once complete so that partial
+/// snapshots are never listed. The database must not be written to while it is copied.
+pub async fn create(dir: &Path, db_file: &Path) -> Result<Snapshot, anyhow::Error> {
+    fs::create_dir_all(dir)
+        .await
+        .context(format!("Failed to create {}!", dir.display()))?;
+
+    let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut file = tempfile::Builder::new()
+        .prefix(".")
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .context("Failed to create snapshot!")?;
+    fs::copy(db_file, file.path())
+        .await
+        .context("Failed to copy database into snapshot!")?;
+    file.as_file()
+        .sync_all()
+        .context("Failed to write snapshot to disk!")?;
+
+    let mut counter = 0;
+    loop {
+        let id = match counter {
+            0 => timestamp.clone(),
+            _ => format!("{}-{}", timestamp, counter),
+        };
+        let path = dir.join(format!("{}{}", id, SNAPSHOT_SUFFIX));
+        match file.persist_noclobber(&path) {
+            Ok(_) => return Ok(Snapshot { id, path }),
+            Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => {
+                file = e.file;
+                counter += 1;
+            }
+            Err(e) => {
+                return Err(anyhow!(e.error).context(format!("Failed to save snapshot {}!", id)))
+            }
+        }
+    }
+}
+
+/// Deletes all but the `keep` most recent snapshots in the directory and returns the deleted
+/// ones.
+pub async fn prune(dir: &Path, keep: usize) -> Result<Vec<Snapshot>, anyhow::Error> {
+    let snapshots = list(dir).await?;
+    let excess = snapshots.len().saturating_sub(keep);
+    let pruned: Vec<Snapshot> = snapshots.into_iter().take(excess).collect();
+    for snapshot in &pruned {
+        fs::remove_file(&snapshot.path)
+            .await
+            .context(format!("Failed to delete snapshot {}!", snapshot.id))?;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "20250102T000000Z.sqlite.db",
+            "20250101T000000Z.sqlite.db",
+            "20250103T000000Z.sqlite.db",
+            ".in-progress.tmp",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), b"").await.unwrap();
+        }
+
+        let ids: Vec<String> = list(dir.path())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec!["20250101T000000Z", "20250102T000000Z", "20250103T000000Z"]
+        );
+        assert!(find(dir.path(), "20250102T000000Z").await.is_ok());
+        assert!(find(dir.path(), "20240101T000000Z").await.is_err());
+
+        let pruned = prune(dir.path(), 2).await.unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].id, "20250101T000000Z");
+        assert_eq!(list(dir.path()).await.unwrap().len(), 2);
+
+        assert!(list(&dir.path().join("missing")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_twice_in_one_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_file = dir.path().join("stations.sqlite.db");
+        fs::write(&db_file, b"stations").await.unwrap();
+        let snapshots_dir = dir.path().join("snapshots");
+
+        let first = create(&snapshots_dir, &db_file).await.unwrap();
+        let second = create(&snapshots_dir, &db_file).await.unwrap();
+
+        assert_ne!(first.id, second.id);
+        assert_eq!(list(&snapshots_dir).await.unwrap(), vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_list_sorts_by_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "20250101T000000Z-10.sqlite.db",
+            "20250101T000000Z-2.sqlite.db",
+            "20250101T000000Z.sqlite.db",
+            "20250101T000001Z.sqlite.db",
+        ] {
+            fs::write(dir.path().join(name), b"").await.unwrap();
+        }
+
+        let ids: Vec<String> = list(dir.path())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "20250101T000000Z",
+                "20250101T000000Z-2",
+                "20250101T000000Z-10",
+                "20250101T000001Z"
+            ]
+        );
+    }
+}
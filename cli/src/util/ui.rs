@@ -1,11 +1,24 @@
 use std::io::Write;
+use std::time::Duration;
+
+/// How often progress is redrawn if the UI is interactive (see [`UI::set_interactive`]).
+const PROGRESS_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// How often progress is logged if the UI is not interactive.
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(5);
+/// The ANSI sequence moving the cursor to the start of the line and clearing the line.
+const CLEAR_LINE: &str = "\r\x1b[2K";
 
 /// A console UI session
 pub struct UI<'a> {
     stdout: &'a mut dyn Write,
     errout: &'a mut dyn Write,
     debug: bool,
+    interactive: bool,
     indentation: usize,
+    /// When progress was last printed, measured from the start of the operation
+    last_progress: Option<Duration>,
+    /// Whether the last line printed is a progress line that is redrawn in place
+    progress_shown: bool,
     log_prefix: String,
     info_prefix: String,
     success_prefix: String,
@@ -54,7 +67,10 @@ impl<'a> UI<'a> {
             stdout,
             errout,
             debug,
+            interactive: false,
             indentation: 0,
+            last_progress: None,
+            progress_shown: false,
             log_prefix,
             info_prefix,
             success_prefix,
@@ -99,6 +115,67 @@ impl<'a> UI<'a> {
         }
     }
 
+    /// Sets whether the output is shown in an interactive terminal.
+    ///
+    /// If it is, progress (see [`UI::progress`]) is shown in a single line that is redrawn in
+    /// place. Otherwise, e.g. when the output is piped into a file, progress is logged
+    /// periodically.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+    }
+
+    /// Reports the progress of a long-running operation.
+    ///
+    /// This can be called as often as necessary as the output is throttled based on
+    /// [`Progress::elapsed`]. Once the operation is done, call [`UI::finish_progress`].
+    ///
+    /// Example:
+    /// ```
+    /// let mut stdout = std::io::stdout();
+    /// let mut stderr = std::io::stderr();
+    /// let mut ui = UI::new(&mut stdout, &mut stderr, true, true);
+    ///
+    /// ui.progress(&Progress {
+    ///     bytes: 4_500_000,
+    ///     total_bytes: Some(9_000_000),
+    ///     rows: 30_000,
+    ///     elapsed: Duration::from_secs(2),
+    /// });
+    ///
+    /// // prints:
+    /// // >    4.5 MB of 9.0 MB (50%), 30000 rows, 15000 rows/s, 2s left
+    /// ```
+    pub fn progress(&mut self, progress: &Progress) {
+        let interval = if self.interactive {
+            PROGRESS_REDRAW_INTERVAL
+        } else {
+            PROGRESS_LOG_INTERVAL
+        };
+        if let Some(last_progress) = self.last_progress {
+            if progress.elapsed.saturating_sub(last_progress) < interval {
+                return;
+            }
+        }
+        self.last_progress = Some(progress.elapsed);
+
+        let indentation = self.indentation();
+        let msg = format!("{}{}{}", indentation, self.log_prefix, progress);
+        if self.interactive {
+            write!(&mut self.stdout, "{}{}", CLEAR_LINE, msg)
+                .and_then(|_| self.stdout.flush())
+                .expect("Cannot write to the output buffer!");
+            self.progress_shown = true;
+        } else {
+            self.out(&msg);
+        }
+    }
+
+    /// Ends reporting the progress of an operation, printing the final throughput.
+    pub fn finish_progress(&mut self, progress: &Progress) {
+        self.last_progress = None;
+        self.log(&progress.summary());
+    }
+
     /// Increases indentation of subsequently printed messages by 2 spaces.
     ///
     /// Example:
@@ -147,19 +224,126 @@ impl<'a> UI<'a> {
     }
 
     fn out(&mut self, msg: &str) {
+        self.clear_progress();
         writeln!(&mut self.stdout, "{}", msg).expect("Cannot write to the output buffer!");
     }
 
     fn errout(&mut self, msg: &str) {
+        self.clear_progress();
         writeln!(&mut self.errout, "{}", msg).expect("Cannot write to the error output buffer!");
     }
+
+    fn clear_progress(&mut self) {
+        if self.progress_shown {
+            write!(&mut self.stdout, "{}", CLEAR_LINE).expect("Cannot write to the output buffer!");
+            self.progress_shown = false;
+        }
+    }
+}
+
+/// The progress of a long-running operation that reads and processes data, see [`UI::progress`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    /// The number of bytes read so far
+    pub bytes: u64,
+    /// The total number of bytes to read if known, e.g. from a `Content-Length` header
+    pub total_bytes: Option<u64>,
+    /// The number of rows processed so far
+    pub rows: u64,
+    /// The time since the operation started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// The average number of rows processed per second.
+    pub fn rows_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.rows as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// The estimated time until all bytes are read, assuming the current throughput.
+    pub fn eta(&self) -> Option<Duration> {
+        let total_bytes = self.total_bytes?;
+        if self.bytes == 0 || self.bytes > total_bytes {
+            return None;
+        }
+        let remaining = (total_bytes - self.bytes) as f64 / self.bytes as f64;
+        Some(self.elapsed.mul_f64(remaining))
+    }
+
+    /// Describes the completed operation, e.g. "Processed 30000 rows (9.0 MB) in 2s, 15000 rows/s".
+    pub fn summary(&self) -> String {
+        format!(
+            "Processed {} rows ({}) in {}, {:.0} rows/s",
+            self.rows,
+            format_bytes(self.bytes),
+            format_duration(self.elapsed),
+            self.rows_per_second()
+        )
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.total_bytes {
+            Some(total_bytes) if total_bytes > 0 => write!(
+                f,
+                "{} of {} ({}%)",
+                format_bytes(self.bytes),
+                format_bytes(total_bytes),
+                (self.bytes.min(total_bytes) * 100) / total_bytes
+            )?,
+            _ => write!(f, "{}", format_bytes(self.bytes))?,
+        }
+        write!(
+            f,
+            ", {} rows, {:.0} rows/s",
+            self.rows,
+            self.rows_per_second()
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", {} left", format_duration(eta))?;
+        }
+        Ok(())
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = UNITS[0];
+    for candidate in UNITS {
+        value /= 1000.0;
+        unit = candidate;
+        if value < 1000.0 {
+            break;
+        }
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::UI;
+    use super::{Progress, UI};
     use anyhow::anyhow;
     use insta::assert_snapshot;
+    use std::time::Duration;
 
     #[test]
     fn test_no_color() {
@@ -238,6 +422,64 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_progress_logged_periodically() {
+        let mut stdout = create_buffer();
+        let mut stderr = create_buffer();
+        let mut ui = UI::new(&mut stdout, &mut stderr, false, false);
+        for seconds in [0, 1, 4, 5, 7, 11] {
+            ui.progress(&Progress {
+                bytes: 1_500_000 * seconds,
+                total_bytes: Some(18_000_000),
+                rows: 1000 * seconds,
+                elapsed: Duration::from_secs(seconds),
+            });
+        }
+        ui.finish_progress(&Progress {
+            bytes: 18_000_000,
+            total_bytes: Some(18_000_000),
+            rows: 12000,
+            elapsed: Duration::from_secs(12),
+        });
+
+        let output = read_buffer(stdout);
+
+        assert_snapshot!(output, @r###"
+        0 B of 18.0 MB (0%), 0 rows, 0 rows/s
+        7.5 MB of 18.0 MB (41%), 5000 rows, 1000 rows/s, 7s left
+        16.5 MB of 18.0 MB (91%), 11000 rows, 1000 rows/s, 1s left
+        Processed 12000 rows (18.0 MB) in 12s, 1000 rows/s
+        "###);
+    }
+
+    #[test]
+    fn test_progress_redrawn_when_interactive() {
+        let mut stdout = create_buffer();
+        let mut stderr = create_buffer();
+        let mut ui = UI::new(&mut stdout, &mut stderr, false, false);
+        ui.set_interactive(true);
+        ui.progress(&Progress {
+            bytes: 2048,
+            total_bytes: None,
+            rows: 10,
+            elapsed: Duration::from_secs(1),
+        });
+        ui.progress(&Progress {
+            bytes: 4096,
+            total_bytes: None,
+            rows: 20,
+            elapsed: Duration::from_secs(2),
+        });
+        ui.log("done");
+
+        let output = read_buffer(stdout);
+
+        assert_eq!(
+            output,
+            "\r\x1b[2K2.0 kB, 10 rows, 10 rows/s\r\x1b[2K4.1 kB, 20 rows, 10 rows/s\r\x1b[2Kdone\n"
+        );
+    }
+
     fn create_buffer() -> std::io::BufWriter<Vec<u8>> {
         std::io::BufWriter::new(Vec::new())
     }
@@ -7,9 +7,11 @@ use figment::{
     Figment,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fmt::{Display, Formatter};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use tracing::info;
 
 /// The application configuration.
@@ -23,7 +25,21 @@ pub struct Config {
     pub server: ServerConfig,
     /// the database configuration: [`DatabaseConfig`]
     pub database: DatabaseConfig,
-    pub source_data_file: String,
+    /// the sources the stations are synchronized from: [`SourceConfig`]
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+    /// the settings for synchronizing the database with the source data: [`SyncConfig`]
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// the settings for reloading the database in the running server: [`ReloadConfig`]
+    #[serde(default)]
+    pub reload: ReloadConfig,
+    /// the settings for keeping snapshots of the synchronized database: [`SnapshotsConfig`]
+    #[serde(default)]
+    pub snapshots: SnapshotsConfig,
+    /// the weights for ranking the stations found by name: [`SearchConfig`]
+    #[serde(default)]
+    pub search: SearchConfig,
 }
 
 /// The server configuration.
@@ -97,6 +113,137 @@ pub struct DatabaseConfig {
     pub url: String,
 }
 
+/// The settings for synchronizing the database with the source data.
+///
+/// `cargo db sync` builds the new dataset in a shadow database first and only swaps it in if it
+/// passes the checks configured here. All settings are optional and default to not restricting the
+/// sync at all.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(default)]
+pub struct SyncConfig {
+    /// The minimum number of stations a synchronized dataset must contain, e.g. 10000
+    pub min_stations: usize,
+    /// The maximum share of existing stations a single sync may delete, e.g. 0.1 for 10%
+    pub max_deleted_ratio: f64,
+    /// The maximum share of rows `cargo db sync --skip-invalid` may skip, e.g. 0.01 for 1%
+    pub max_rejected_ratio: f64,
+    /// The TOML file with local corrections applied on top of the source data, see
+    /// `restations_cli::overrides`
+    pub overrides_file: Option<PathBuf>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            min_stations: 0,
+            max_deleted_ratio: 1.0,
+            max_rejected_ratio: 1.0,
+            overrides_file: None,
+        }
+    }
+}
+
+/// A source the stations are synchronized from.
+///
+/// When several sources are configured, `cargo db sync` matches their stations by UIC or carrier
+/// IDs and takes each field's value from the source with the highest priority for that field.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct SourceConfig {
+    /// Identifies the source, e.g. in the provenance of a station's name and coordinates
+    pub name: String,
+    /// The format of the source data: [`SourceFormat`]
+    #[serde(default)]
+    pub format: SourceFormat,
+    /// The URL the source data is downloaded from; local files can be passed as `file://` URLs
+    pub url: String,
+    /// Higher priority sources win when several sources provide a value for a field, e.g. 100
+    #[serde(default)]
+    pub priority: i64,
+    /// Priorities for particular fields that override `priority`, e.g. `{ coordinates = 200 }`
+    #[serde(default)]
+    pub field_priorities: HashMap<String, i64>,
+    /// The full SHA of the upstream commit to download `url` from instead of the branch it
+    /// points at. This is only supported for raw.githubusercontent.com URLs.
+    pub commit: Option<String>,
+    /// The hex-encoded SHA-256 digest the source data must have, e.g. to make builds reproducible
+    pub sha256: Option<String>,
+}
+
+/// The settings for reloading the database in the running server.
+///
+/// The server always reopens the database when it receives `SIGHUP`. Reloading via the admin
+/// endpoint or when the database file changes needs to be enabled here.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(default)]
+pub struct ReloadConfig {
+    /// How often to check whether the database file was replaced, in seconds; 0 disables the check
+    pub watch_interval: u64,
+    /// The bearer token `POST /admin/reload` requires; the endpoint rejects all requests if unset
+    pub admin_token: Option<String>,
+}
+
+/// The settings for keeping snapshots of the synchronized database.
+///
+/// `cargo db sync` saves a copy of each synchronized database that `cargo db rollback <snapshot>`
+/// can restore later, e.g. after a bad upstream update.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(default)]
+pub struct SnapshotsConfig {
+    /// The directory the snapshots are kept in, e.g. "snapshots"
+    pub dir: PathBuf,
+    /// The number of snapshots to keep, older ones are deleted; 0 disables snapshots
+    pub keep: usize,
+}
+
+impl Default for SnapshotsConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("snapshots"),
+            keep: 0,
+        }
+    }
+}
+
+/// The weights for ranking the stations found by name.
+///
+/// Each station's score is the sum of the weights of the criteria it meets; stations with equal
+/// scores are ranked by how well their names match (BM25). An exact match also counts as a prefix
+/// match. Searches by name and position rank the stations by distance only.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(default)]
+pub struct SearchConfig {
+    /// The station's name or one of its translations is the searched name
+    pub exact_match: f64,
+    /// The station's name or one of its translations starts with the searched name
+    pub prefix_match: f64,
+    /// All searched words are found in the station's name rather than only in its translations
+    pub primary_name: f64,
+    /// The station is a main station
+    pub main_station: f64,
+    /// The station represents a city rather than a particular station
+    pub city: f64,
+    /// The station is suggested to users, e.g. in autocompletion
+    pub suggestable: f64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            exact_match: 100.0,
+            prefix_match: 50.0,
+            primary_name: 20.0,
+            main_station: 15.0,
+            city: 10.0,
+            suggestable: 5.0,
+        }
+    }
+}
+
 /// Loads the application configuration for a particular environment.
 ///
 /// Depending on the environment, this function will behave differently:
@@ -181,6 +328,39 @@ impl Display for Environment {
     }
 }
 
+/// The formats source data can be imported from.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceFormat {
+    /// Trainline EU's stations dataset, a semicolon-separated CSV file
+    #[default]
+    Trainline,
+    /// A GTFS feed, a zip file of which the stops in `stops.txt` are imported
+    Gtfs,
+    /// A NeTEx XML file, of which the `StopPlace` and `Quay` elements are imported
+    Netex,
+}
+
+impl Display for SourceFormat {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SourceFormat::Trainline => write!(f, "trainline"),
+            SourceFormat::Gtfs => write!(f, "gtfs"),
+            SourceFormat::Netex => write!(f, "netex"),
+        }
+    }
+}
+
+/// Parses a [`SourceFormat`] from a string, e.g. "trainline" or "netex".
+pub fn parse_source_format(format: &str) -> Result<SourceFormat, anyhow::Error> {
+    match format.to_lowercase().as_str() {
+        "trainline" => Ok(SourceFormat::Trainline),
+        "gtfs" => Ok(SourceFormat::Gtfs),
+        "netex" => Ok(SourceFormat::Netex),
+        unknown => Err(anyhow!(r#"Unknown source format: "{}"!"#, unknown)),
+    }
+}
+
 /// Returns the currently active environment.
 ///
 /// If the `APP_ENVIRONMENT` env var is set, the application environment is parsed from that (which might fail if an invalid environment is set). If the env var is not set, [`Environment::Development`] is returned.
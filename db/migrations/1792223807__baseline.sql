@@ -0,0 +1,50 @@
+-- migrate:up
+-- Brings a database created from the schema that preceded versioned migrations up to the schema
+-- `cargo db create` created when they were introduced. Databases that already have that schema get
+-- this migration recorded without running it, see `migrate` in cli/src/bin/db.rs.
+ALTER TABLE stations ADD COLUMN slug TEXT;
+ALTER TABLE stations ADD COLUMN uic8_sncf TEXT;
+ALTER TABLE stations ADD COLUMN parent_station_id INTEGER;
+ALTER TABLE stations ADD COLUMN time_zone TEXT;
+ALTER TABLE stations ADD COLUMN is_city BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN is_main_station BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN is_airport BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN is_suggestable BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN country_hint BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN main_station_hint BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE stations ADD COLUMN same_as INTEGER;
+
+CREATE TABLE alternative_ids (
+    station_id INTEGER NOT NULL REFERENCES stations (id) ON DELETE CASCADE,
+    scheme TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (station_id, scheme)
+);
+
+CREATE INDEX alternative_ids_scheme_value_idx ON alternative_ids (scheme, value);
+
+CREATE TABLE dataset_metadata (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    source TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    sha256 TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    synced_at TEXT NOT NULL
+);
+
+-- migrate:down
+DROP TABLE dataset_metadata;
+DROP TABLE alternative_ids;
+
+ALTER TABLE stations DROP COLUMN same_as;
+ALTER TABLE stations DROP COLUMN main_station_hint;
+ALTER TABLE stations DROP COLUMN country_hint;
+ALTER TABLE stations DROP COLUMN is_suggestable;
+ALTER TABLE stations DROP COLUMN is_airport;
+ALTER TABLE stations DROP COLUMN is_main_station;
+ALTER TABLE stations DROP COLUMN is_city;
+ALTER TABLE stations DROP COLUMN time_zone;
+ALTER TABLE stations DROP COLUMN parent_station_id;
+ALTER TABLE stations DROP COLUMN uic8_sncf;
+ALTER TABLE stations DROP COLUMN slug;
@@ -0,0 +1,45 @@
+-- migrate:up
+CREATE TABLE station_provenance (
+    station_id INTEGER NOT NULL REFERENCES stations (id) ON DELETE CASCADE,
+    field TEXT NOT NULL,
+    source TEXT NOT NULL,
+    PRIMARY KEY (station_id, field)
+);
+
+CREATE TABLE dataset_metadata_by_source (
+    name TEXT PRIMARY KEY,
+    priority INTEGER NOT NULL DEFAULT 0,
+    source TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    sha256 TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    synced_at TEXT NOT NULL
+);
+
+INSERT INTO dataset_metadata_by_source (name, source, etag, last_modified, sha256, row_count, synced_at)
+SELECT 'trainline', source, etag, last_modified, sha256, row_count, synced_at FROM dataset_metadata;
+
+DROP TABLE dataset_metadata;
+ALTER TABLE dataset_metadata_by_source RENAME TO dataset_metadata;
+
+-- migrate:down
+CREATE TABLE dataset_metadata_single (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    source TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    sha256 TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    synced_at TEXT NOT NULL
+);
+
+-- only the source with the highest priority is kept
+INSERT INTO dataset_metadata_single (id, source, etag, last_modified, sha256, row_count, synced_at)
+SELECT 1, source, etag, last_modified, sha256, row_count, synced_at FROM dataset_metadata
+ORDER BY priority DESC LIMIT 1;
+
+DROP TABLE dataset_metadata;
+ALTER TABLE dataset_metadata_single RENAME TO dataset_metadata;
+
+DROP TABLE station_provenance;
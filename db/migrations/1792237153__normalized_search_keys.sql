@@ -0,0 +1,231 @@
+-- migrate:up
+-- The index is filled with the search keys computed by `cargo db migrate` and `cargo db sync`.
+DROP TRIGGER stations_fts_update;
+DROP TRIGGER stations_fts_delete;
+DROP TRIGGER stations_fts_insert;
+DROP TABLE stations_fts;
+
+CREATE VIRTUAL TABLE stations_fts USING fts5 (
+    name,
+    translations,
+    tokenize = 'unicode61 remove_diacritics 0'
+);
+
+-- migrate:down
+DROP TABLE stations_fts;
+
+CREATE VIRTUAL TABLE stations_fts USING fts5 (
+    name,
+    info_de,
+    info_en,
+    info_es,
+    info_fr,
+    info_it,
+    info_nb,
+    info_nl,
+    info_cs,
+    info_da,
+    info_hu,
+    info_ja,
+    info_ko,
+    info_pl,
+    info_pt,
+    info_ru,
+    info_sv,
+    info_tr,
+    info_zh,
+    content = 'stations',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER stations_fts_insert AFTER INSERT ON stations BEGIN
+    INSERT INTO stations_fts (
+        rowid,
+        name,
+        info_de,
+        info_en,
+        info_es,
+        info_fr,
+        info_it,
+        info_nb,
+        info_nl,
+        info_cs,
+        info_da,
+        info_hu,
+        info_ja,
+        info_ko,
+        info_pl,
+        info_pt,
+        info_ru,
+        info_sv,
+        info_tr,
+        info_zh
+    ) VALUES (
+        new.id,
+        new.name,
+        new.info_de,
+        new.info_en,
+        new.info_es,
+        new.info_fr,
+        new.info_it,
+        new.info_nb,
+        new.info_nl,
+        new.info_cs,
+        new.info_da,
+        new.info_hu,
+        new.info_ja,
+        new.info_ko,
+        new.info_pl,
+        new.info_pt,
+        new.info_ru,
+        new.info_sv,
+        new.info_tr,
+        new.info_zh
+    );
+END;
+
+CREATE TRIGGER stations_fts_delete AFTER DELETE ON stations BEGIN
+    INSERT INTO stations_fts (
+        stations_fts,
+        rowid,
+        name,
+        info_de,
+        info_en,
+        info_es,
+        info_fr,
+        info_it,
+        info_nb,
+        info_nl,
+        info_cs,
+        info_da,
+        info_hu,
+        info_ja,
+        info_ko,
+        info_pl,
+        info_pt,
+        info_ru,
+        info_sv,
+        info_tr,
+        info_zh
+    ) VALUES (
+        'delete',
+        old.id,
+        old.name,
+        old.info_de,
+        old.info_en,
+        old.info_es,
+        old.info_fr,
+        old.info_it,
+        old.info_nb,
+        old.info_nl,
+        old.info_cs,
+        old.info_da,
+        old.info_hu,
+        old.info_ja,
+        old.info_ko,
+        old.info_pl,
+        old.info_pt,
+        old.info_ru,
+        old.info_sv,
+        old.info_tr,
+        old.info_zh
+    );
+END;
+
+CREATE TRIGGER stations_fts_update AFTER UPDATE OF
+    id, name, info_de, info_en, info_es, info_fr, info_it, info_nb, info_nl, info_cs, info_da, info_hu, info_ja,
+    info_ko, info_pl, info_pt, info_ru, info_sv, info_tr, info_zh
+ON stations BEGIN
+    INSERT INTO stations_fts (
+        stations_fts,
+        rowid,
+        name,
+        info_de,
+        info_en,
+        info_es,
+        info_fr,
+        info_it,
+        info_nb,
+        info_nl,
+        info_cs,
+        info_da,
+        info_hu,
+        info_ja,
+        info_ko,
+        info_pl,
+        info_pt,
+        info_ru,
+        info_sv,
+        info_tr,
+        info_zh
+    ) VALUES (
+        'delete',
+        old.id,
+        old.name,
+        old.info_de,
+        old.info_en,
+        old.info_es,
+        old.info_fr,
+        old.info_it,
+        old.info_nb,
+        old.info_nl,
+        old.info_cs,
+        old.info_da,
+        old.info_hu,
+        old.info_ja,
+        old.info_ko,
+        old.info_pl,
+        old.info_pt,
+        old.info_ru,
+        old.info_sv,
+        old.info_tr,
+        old.info_zh
+    );
+    INSERT INTO stations_fts (
+        rowid,
+        name,
+        info_de,
+        info_en,
+        info_es,
+        info_fr,
+        info_it,
+        info_nb,
+        info_nl,
+        info_cs,
+        info_da,
+        info_hu,
+        info_ja,
+        info_ko,
+        info_pl,
+        info_pt,
+        info_ru,
+        info_sv,
+        info_tr,
+        info_zh
+    ) VALUES (
+        new.id,
+        new.name,
+        new.info_de,
+        new.info_en,
+        new.info_es,
+        new.info_fr,
+        new.info_it,
+        new.info_nb,
+        new.info_nl,
+        new.info_cs,
+        new.info_da,
+        new.info_hu,
+        new.info_ja,
+        new.info_ko,
+        new.info_pl,
+        new.info_pt,
+        new.info_ru,
+        new.info_sv,
+        new.info_tr,
+        new.info_zh
+    );
+END;
+
+INSERT INTO stations_fts (stations_fts) VALUES ('rebuild');
+
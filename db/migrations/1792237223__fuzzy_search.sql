@@ -0,0 +1,5 @@
+-- migrate:up
+CREATE VIRTUAL TABLE stations_fts_vocab USING fts5vocab (stations_fts, row);
+
+-- migrate:down
+DROP TABLE stations_fts_vocab;
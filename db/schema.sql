@@ -1,10 +1,21 @@
 CREATE TABLE stations (
     id INTEGER PRIMARY KEY,
     name TEXT NOT NULL,
+    slug TEXT,
     uic TEXT NOT NULL,
+    uic8_sncf TEXT,
     latitude REAL,
     longitude REAL,
+    parent_station_id INTEGER,
     country TEXT,
+    time_zone TEXT,
+    is_city BOOLEAN NOT NULL DEFAULT FALSE,
+    is_main_station BOOLEAN NOT NULL DEFAULT FALSE,
+    is_airport BOOLEAN NOT NULL DEFAULT FALSE,
+    is_suggestable BOOLEAN NOT NULL DEFAULT FALSE,
+    country_hint BOOLEAN NOT NULL DEFAULT FALSE,
+    main_station_hint BOOLEAN NOT NULL DEFAULT FALSE,
+    same_as INTEGER,
     info_de TEXT,
     info_en TEXT,
     info_es TEXT,
@@ -26,3 +37,52 @@ CREATE TABLE stations (
 );
 
 CREATE UNIQUE INDEX stations_id_idx ON stations (id);
+
+-- Full-text index over the search keys of the stations' names, see `restations_db::search`: `name`
+-- holds the key of the primary name and `translations` those of the localized names. Rows are
+-- keyed by the station ID and the index is rebuilt by `cargo db sync`.
+CREATE VIRTUAL TABLE stations_fts USING fts5 (
+    name,
+    translations,
+    tokenize = 'unicode61 remove_diacritics 0'
+);
+
+-- The words in `stations_fts`, which fuzzy searches look for words similar to the searched ones in.
+CREATE VIRTUAL TABLE stations_fts_vocab USING fts5vocab (stations_fts, row);
+
+CREATE TABLE alternative_ids (
+    station_id INTEGER NOT NULL REFERENCES stations (id) ON DELETE CASCADE,
+    scheme TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (station_id, scheme)
+);
+
+CREATE INDEX alternative_ids_scheme_value_idx ON alternative_ids (scheme, value);
+
+-- Which source each of a station's name and coordinates was taken from when merging sources.
+CREATE TABLE station_provenance (
+    station_id INTEGER NOT NULL REFERENCES stations (id) ON DELETE CASCADE,
+    field TEXT NOT NULL,
+    source TEXT NOT NULL,
+    PRIMARY KEY (station_id, field)
+);
+
+-- Describes the datasets the stations were last synchronized from, one row per source.
+CREATE TABLE dataset_metadata (
+    name TEXT PRIMARY KEY,
+    priority INTEGER NOT NULL DEFAULT 0,
+    source TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    sha256 TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    synced_at TEXT NOT NULL
+);
+
+-- The migrations from db/migrations that have been applied to the database. `cargo db create` marks
+-- all existing migrations as applied since this file already reflects them.
+CREATE TABLE schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL
+);
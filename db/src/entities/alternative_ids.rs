@@ -0,0 +1,83 @@
+#[cfg(feature = "test-helpers")]
+use fake::{faker::lorem::en::Word, Dummy};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Sqlite;
+use validator::Validate;
+
+/// An identifier of a station in a carrier's or vendor's own ID scheme, e.g. SNCF's `FRPST` for
+/// Paris Gare de l'Est.
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct AlternativeId {
+    pub station_id: i64,
+    pub scheme: String,
+    pub value: String,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize, Dummy))]
+pub struct AlternativeIdChangeset {
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "100..1000000"))]
+    pub station_id: i64,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "Word()"))]
+    #[validate(length(min = 1))]
+    pub scheme: String,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "Word()"))]
+    #[validate(length(min = 1))]
+    pub value: String,
+}
+
+/// Loads all alternative IDs, ordered by station and scheme.
+pub async fn load_all(
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<Vec<AlternativeId>, crate::Error> {
+    let alternative_ids = sqlx::query_as!(
+        AlternativeId,
+        "SELECT
+            station_id,
+            scheme,
+            value
+        FROM
+            alternative_ids
+        ORDER BY
+            station_id,
+            scheme"
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(alternative_ids)
+}
+
+/// Loads the alternative IDs of all stations with the given IDs, ordered by station and scheme.
+pub async fn load_for_stations(
+    station_ids: &[i64],
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<Vec<AlternativeId>, crate::Error> {
+    // SQLite cannot bind arrays so the IDs are passed as a JSON array instead.
+    let station_ids = format!(
+        "[{}]",
+        station_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let alternative_ids = sqlx::query_as!(
+        AlternativeId,
+        "SELECT
+            station_id,
+            scheme,
+            value
+        FROM
+            alternative_ids
+        WHERE
+            station_id IN (SELECT value FROM json_each(?))
+        ORDER BY
+            station_id,
+            scheme",
+        station_ids
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(alternative_ids)
+}
@@ -0,0 +1,93 @@
+#[cfg(feature = "test-helpers")]
+use fake::{faker::internet::en::DomainSuffix, Dummy};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Sqlite;
+use validator::Validate;
+
+/// Describes a dataset the stations were last synchronized from by `cargo db sync`.
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct DatasetMetadata {
+    /// The name of the source as configured
+    pub name: String,
+    /// The source's priority when merging sources
+    pub priority: i64,
+    /// The URL or path the data was read from
+    pub source: String,
+    /// The `ETag` header the data was served with if it was downloaded
+    pub etag: Option<String>,
+    /// The `Last-Modified` header the data was served with if it was downloaded
+    pub last_modified: Option<String>,
+    /// The hex-encoded SHA-256 digest of the data as read from the source
+    pub sha256: String,
+    /// The number of stations read from the source
+    pub row_count: i64,
+    /// When the synchronization finished in RFC 3339 format
+    pub synced_at: String,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize, Dummy))]
+pub struct DatasetMetadataChangeset {
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "DomainSuffix()"))]
+    #[validate(length(min = 1))]
+    pub name: String,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "0..1000"))]
+    pub priority: i64,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "DomainSuffix()"))]
+    #[validate(length(min = 1))]
+    pub source: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    #[cfg_attr(
+        feature = "test-helpers",
+        dummy(
+            expr = "\"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\".into()"
+        )
+    )]
+    #[validate(length(equal = 64))]
+    pub sha256: String,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "0..100000"))]
+    pub row_count: i64,
+    #[cfg_attr(
+        feature = "test-helpers",
+        dummy(expr = "\"2025-01-01T00:00:00Z\".into()")
+    )]
+    #[validate(length(min = 1))]
+    pub synced_at: String,
+}
+
+/// Loads the metadata of the datasets the stations were synchronized from, highest priority
+/// first.
+///
+/// Returns [`crate::Error::NoRecordFound`] if the database has never been synchronized.
+pub async fn load_all(
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<Vec<DatasetMetadata>, crate::Error> {
+    let metadata = sqlx::query_as!(
+        DatasetMetadata,
+        "SELECT
+            name AS \"name!\",
+            priority,
+            source,
+            etag,
+            last_modified,
+            sha256,
+            row_count,
+            synced_at
+        FROM
+            dataset_metadata
+        ORDER BY
+            priority DESC,
+            name"
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(crate::Error::DbError)?;
+
+    if metadata.is_empty() {
+        Err(crate::Error::NoRecordFound)
+    } else {
+        Ok(metadata)
+    }
+}
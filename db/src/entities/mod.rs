@@ -1 +1,3 @@
+pub mod alternative_ids;
+pub mod dataset_metadata;
 pub mod stations;
@@ -1,11 +1,15 @@
+use crate::search::search_key;
 #[cfg(feature = "test-helpers")]
 use fake::{
     faker::{address::en::*, number::en::NumberWithFormat},
     Dummy,
 };
+use restations_config::SearchConfig;
 use serde::Deserialize;
 use serde::Serialize;
-use sqlx::Sqlite;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Connection, Sqlite};
+use std::collections::HashSet;
 use validator::Validate;
 
 // Approximate distance measured in degrees of latitude/longitude.
@@ -16,10 +20,21 @@ pub const APPROXIMATE_DISTANCE: f32 = 1.0; // Roughly 100km at equator
 pub struct Station {
     pub id: i64,
     pub name: String,
+    pub slug: Option<String>,
     pub uic: String,
+    pub uic8_sncf: Option<String>,
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
+    pub parent_station_id: Option<i64>,
     pub country: Option<String>,
+    pub time_zone: Option<String>,
+    pub is_city: bool,
+    pub is_main_station: bool,
+    pub is_airport: bool,
+    pub is_suggestable: bool,
+    pub country_hint: bool,
+    pub main_station_hint: bool,
+    pub same_as: Option<i64>,
     pub info_de: Option<String>,
     pub info_en: Option<String>,
     pub info_es: Option<String>,
@@ -48,17 +63,31 @@ pub struct StationChangeset {
     #[cfg_attr(feature = "test-helpers", dummy(faker = "CityName()"))]
     #[validate(length(min = 1))]
     pub name: String,
+    pub slug: Option<String>,
     #[cfg_attr(
         feature = "test-helpers",
         dummy(faker = "NumberWithFormat(\"^######\")")
     )]
     pub uic: String,
+    pub uic8_sncf: Option<String>,
     #[cfg_attr(feature = "test-helpers", dummy(faker = "Latitude()"))]
     pub latitude: Option<f64>,
     #[cfg_attr(feature = "test-helpers", dummy(faker = "Longitude()"))]
     pub longitude: Option<f64>,
+    #[cfg_attr(feature = "test-helpers", dummy(default))]
+    pub parent_station_id: Option<i64>,
     #[cfg_attr(feature = "test-helpers", dummy(faker = "CountryName()"))]
     pub country: Option<String>,
+    #[cfg_attr(feature = "test-helpers", dummy(faker = "TimeZone()"))]
+    pub time_zone: Option<String>,
+    pub is_city: bool,
+    pub is_main_station: bool,
+    pub is_airport: bool,
+    pub is_suggestable: bool,
+    pub country_hint: bool,
+    pub main_station_hint: bool,
+    #[cfg_attr(feature = "test-helpers", dummy(default))]
+    pub same_as: Option<i64>,
     pub info_de: Option<String>,
     pub info_en: Option<String>,
     pub info_es: Option<String>,
@@ -79,6 +108,35 @@ pub struct StationChangeset {
     pub info_zh: Option<String>,
 }
 
+impl Station {
+    /// The station's localized names.
+    pub fn translations(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.info_de,
+            &self.info_en,
+            &self.info_es,
+            &self.info_fr,
+            &self.info_it,
+            &self.info_nb,
+            &self.info_nl,
+            &self.info_cs,
+            &self.info_da,
+            &self.info_hu,
+            &self.info_ja,
+            &self.info_ko,
+            &self.info_pl,
+            &self.info_pt,
+            &self.info_ru,
+            &self.info_sv,
+            &self.info_tr,
+            &self.info_zh,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+    }
+}
+
 pub async fn load_all(
     executor: impl sqlx::Executor<'_, Database = Sqlite>,
 ) -> Result<Vec<Station>, crate::Error> {
@@ -87,10 +145,21 @@ pub async fn load_all(
         "SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -126,10 +195,21 @@ pub async fn load_all_within_limit(
         "SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -159,7 +239,8 @@ pub async fn load_all_within_limit(
     Ok(stations)
 }
 
-pub async fn load(
+/// Loads the station with the passed station ID; use [`load_by_uic`] to look stations up by UIC.
+pub async fn load_by_id(
     id: i64,
     executor: impl sqlx::Executor<'_, Database = Sqlite>,
 ) -> Result<Station, crate::Error> {
@@ -168,10 +249,21 @@ pub async fn load(
         "SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -193,7 +285,7 @@ pub async fn load(
         FROM
             stations
         WHERE
-            uic = ?",
+            id = ?",
         id
     )
     .fetch_optional(executor)
@@ -205,21 +297,35 @@ pub async fn load(
     }
 }
 
-pub async fn search_by_name(
-    name: &str,
-    limit: i32,
+/// Loads the station with the passed UIC.
+///
+/// UICs are not unique, e.g. a city often shares its UIC with its main station. If several
+/// stations have the UIC, non-city stations are preferred over cities and main stations over
+/// other stations, with the lowest station ID breaking ties.
+pub async fn load_by_uic(
+    uic: &str,
     executor: impl sqlx::Executor<'_, Database = Sqlite>,
-) -> Result<Vec<Station>, crate::Error> {
-    let pattern = format!("%{}%", name);
-    let stations = sqlx::query_as!(
+) -> Result<Station, crate::Error> {
+    match sqlx::query_as!(
         Station,
         "SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -241,64 +347,82 @@ pub async fn search_by_name(
         FROM
             stations
         WHERE
-            uic IS NOT NULL
-        AND (
-            name LIKE $1
-            OR info_de LIKE $1
-            OR info_en LIKE $1
-            OR info_es LIKE $1
-            OR info_fr LIKE $1
-            OR info_it LIKE $1
-            OR info_nb LIKE $1
-            OR info_nl LIKE $1
-            OR info_cs LIKE $1
-            OR info_da LIKE $1
-            OR info_hu LIKE $1
-            OR info_ja LIKE $1
-            OR info_ko LIKE $1
-            OR info_pl LIKE $1
-            OR info_pt LIKE $1
-            OR info_ru LIKE $1
-            OR info_sv LIKE $1
-            OR info_tr LIKE $1
-            OR info_zh LIKE $1
-        )
+            uic = ?
         ORDER BY
-            name
-        ASC
-        LIMIT
-            $2",
-        pattern,
-        limit,
+            is_city,
+            is_main_station DESC,
+            id
+        LIMIT 1",
+        uic
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(station) => Ok(station),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Returns the IDs of those of the given stations whose UIC is shared with another station, so
+/// that their UIC does not identify them.
+pub async fn load_ids_with_shared_uic(
+    station_ids: &[i64],
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<HashSet<i64>, crate::Error> {
+    // SQLite cannot bind arrays so the IDs are passed as a JSON array instead.
+    let station_ids = format!(
+        "[{}]",
+        station_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let ids = sqlx::query_scalar!(
+        "SELECT
+            stations.id AS \"id!\"
+        FROM
+            stations
+        WHERE
+            stations.id IN (SELECT value FROM json_each(?))
+            AND stations.uic != ''
+            AND EXISTS (
+                SELECT 1 FROM stations AS others
+                WHERE others.uic = stations.uic AND others.id != stations.id
+            )",
+        station_ids
     )
     .fetch_all(executor)
     .await?;
-    Ok(stations)
+    Ok(ids.into_iter().collect())
 }
 
-pub async fn search_by_position(
-    latitude: f64,
-    longitude: f64,
-    limit: i32,
+pub async fn load_by_alternative_id(
+    scheme: &str,
+    value: &str,
     executor: impl sqlx::Executor<'_, Database = Sqlite>,
-) -> Result<Vec<Station>, crate::Error> {
-    // First, get a larger set of candidates using a bounding box
-    // This is more efficient for the initial filtering
-    //
-    // TODO: extract into a constant that is visible
-    // and perhaps configurable as an env var
-    let approx_distance_deg = APPROXIMATE_DISTANCE;
-
-    let stations = sqlx::query_as!(
+) -> Result<Station, crate::Error> {
+    match sqlx::query_as!(
         Station,
-        r#"
-        SELECT
+        "SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -320,54 +444,342 @@ pub async fn search_by_position(
         FROM
             stations
         WHERE
-            latitude IS NOT NULL
-            AND longitude IS NOT NULL
-            AND latitude BETWEEN $1 - $3 AND $1 + $3
-            AND longitude BETWEEN $2 - $3 AND $2 + $3
+            id = (
+                SELECT
+                    station_id
+                FROM
+                    alternative_ids
+                WHERE
+                    scheme = ?
+                    AND value = ?
+                LIMIT
+                    1
+            )",
+        scheme,
+        value
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(station) => Ok(station),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Searches stations with names in any language containing words starting with the words in
+/// `name`, ignoring case and diacritics, best matches first.
+///
+/// Matches are ranked by the sum of the `weights` of the criteria they meet and then by BM25 over
+/// the full-text index `stations_fts`. With `fuzzy`, stations with names containing words a few
+/// typos away from those in `name` are found, too, and ranked below all other matches.
+pub async fn search_by_name(
+    name: &str,
+    limit: i32,
+    fuzzy: bool,
+    weights: &SearchConfig,
+    db: impl sqlx::Acquire<'_, Database = Sqlite>,
+) -> Result<Vec<Station>, crate::Error> {
+    let Some(query) = full_text_query(name) else {
+        return Ok(Vec::new());
+    };
+    let phrase = words(name).join(" ");
+    let mut conn = db.acquire().await?;
+    let mut stations =
+        search_by_full_text_query(&query, &phrase, limit, weights, &mut *conn).await?;
+    if fuzzy && stations.len() < limit as usize {
+        if let Some(query) = fuzzy_full_text_query(name, &mut conn).await? {
+            let fuzzy_limit = limit + stations.len() as i32;
+            let found =
+                search_by_full_text_query(&query, &phrase, fuzzy_limit, weights, &mut *conn)
+                    .await?;
+            append_fuzzy_matches(&mut stations, found, limit);
+        }
+    }
+    Ok(stations)
+}
+
+/// Searches stations matching the FTS5 `query`, ranked as described in [`search_by_name`];
+/// `phrase` are the searched words as they are stored in the index.
+async fn search_by_full_text_query(
+    query: &str,
+    phrase: &str,
+    limit: i32,
+    weights: &SearchConfig,
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<Vec<Station>, crate::Error> {
+    let primary_name_query = format!("name : ({})", query);
+    let stations = sqlx::query_as!(
+        Station,
+        "SELECT
+            stations.id,
+            stations.name,
+            stations.slug,
+            stations.uic,
+            stations.uic8_sncf,
+            stations.latitude,
+            stations.longitude,
+            stations.parent_station_id,
+            stations.country,
+            stations.time_zone,
+            stations.is_city,
+            stations.is_main_station,
+            stations.is_airport,
+            stations.is_suggestable,
+            stations.country_hint,
+            stations.main_station_hint,
+            stations.same_as,
+            stations.info_de,
+            stations.info_en,
+            stations.info_es,
+            stations.info_fr,
+            stations.info_it,
+            stations.info_nb,
+            stations.info_nl,
+            stations.info_cs,
+            stations.info_da,
+            stations.info_hu,
+            stations.info_ja,
+            stations.info_ko,
+            stations.info_pl,
+            stations.info_pt,
+            stations.info_ru,
+            stations.info_sv,
+            stations.info_tr,
+            stations.info_zh
+        FROM
+            stations_fts
+            JOIN stations ON stations.id = stations_fts.rowid
+        WHERE
+            stations_fts MATCH $1
         ORDER BY
-            ((latitude - $1) * (latitude - $1)) +
-            ((longitude - $2) * (longitude - $2))
-        ASC
+            CASE
+                WHEN stations_fts.name = $3
+                    OR instr(char(10) || stations_fts.translations || char(10), char(10) || $3 || char(10)) > 0
+                THEN $5 ELSE 0
+            END
+            + CASE
+                WHEN substr(stations_fts.name, 1, length($3)) = $3
+                    OR instr(char(10) || stations_fts.translations, char(10) || $3) > 0
+                THEN $6 ELSE 0
+            END
+            + CASE
+                WHEN stations.id IN (SELECT rowid FROM stations_fts WHERE stations_fts MATCH $4)
+                THEN $7 ELSE 0
+            END
+            + CASE WHEN stations.is_main_station THEN $8 ELSE 0 END
+            + CASE WHEN stations.is_city THEN $9 ELSE 0 END
+            + CASE WHEN stations.is_suggestable THEN $10 ELSE 0 END
+            DESC,
+            stations_fts.rank,
+            stations.name
         LIMIT
-            $4
-        "#,
-        latitude,
-        longitude,
-        approx_distance_deg,
-        limit
+            $2",
+        query,
+        limit,
+        phrase,
+        primary_name_query,
+        weights.exact_match,
+        weights.prefix_match,
+        weights.primary_name,
+        weights.main_station,
+        weights.city,
+        weights.suggestable,
     )
     .fetch_all(executor)
     .await?;
-
-    // Return the closest 20
     Ok(stations)
 }
 
-pub async fn search_by_name_and_position(
+/// Builds an FTS5 query from the search keys of the words in `name`, each matching any word
+/// starting with it.
+///
+/// Words are quoted so that FTS5 syntax in user input is matched literally. Returns `None` if
+/// `name` contains no words.
+fn full_text_query(name: &str) -> Option<String> {
+    let terms: Vec<String> = words(name)
+        .iter()
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Builds an FTS5 query like [`full_text_query`] that also matches the indexed words within
+/// [`max_typos`] of each word in `name`.
+///
+/// Only indexed words that start with the same character as the searched word are considered, so
+/// that the vocabulary is narrowed down by its index instead of being scanned for every word; a
+/// typo in the first character is not tolerated.
+///
+/// Returns `None` if no indexed word is close enough to any of the words to find more stations.
+async fn fuzzy_full_text_query(
     name: &str,
+    conn: &mut SqliteConnection,
+) -> Result<Option<String>, crate::Error> {
+    let words = words(name);
+    let mut corrected = false;
+    let mut terms = Vec::with_capacity(words.len());
+    for word in &words {
+        let mut alternatives = vec![format!("\"{}\"*", word)];
+        let length = word.chars().count() as i64;
+        let typos = max_typos(word);
+        if typos > 0 {
+            let (min_length, max_length) = (length - typos as i64, length + typos as i64);
+            let (from, to) = first_character_range(word);
+            let candidates = sqlx::query_scalar!(
+                r#"
+                SELECT
+                    term AS "term!: String"
+                FROM
+                    stations_fts_vocab
+                WHERE
+                    term >= $1
+                    AND term < $2
+                    AND length(term) BETWEEN $3 AND $4
+                "#,
+                from,
+                to,
+                min_length,
+                max_length
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+            for candidate in candidates {
+                if candidate != *word && strsim::osa_distance(word, &candidate) <= typos {
+                    alternatives.push(format!("\"{}\"", candidate));
+                    corrected = true;
+                }
+            }
+        }
+        terms.push(format!("({})", alternatives.join(" OR ")));
+    }
+    if corrected {
+        Ok(Some(terms.join(" AND ")))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The range of terms starting with the first character of `word`, e.g. `("f", "g")` for
+/// `"frankfurt"`.
+fn first_character_range(word: &str) -> (String, String) {
+    let first = word.chars().next().unwrap_or_default();
+    let next = (first as u32 + 1..=char::MAX as u32)
+        .find_map(char::from_u32)
+        .unwrap_or(char::MAX);
+    (first.to_string(), next.to_string())
+}
+
+/// Splits the search key of `name` into words the way the full-text index does.
+fn words(name: &str) -> Vec<String> {
+    search_key(name)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// How many typos, i.e. inserted, deleted, replaced or swapped characters, a fuzzy search
+/// tolerates in a word; short words must match exactly as nearly all words would be found
+/// otherwise.
+fn max_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Appends the stations found by a fuzzy search that are not among the stations found by an
+/// exact search, up to `limit` stations in total.
+fn append_fuzzy_matches(stations: &mut Vec<Station>, fuzzy_matches: Vec<Station>, limit: i32) {
+    let found: HashSet<i64> = stations.iter().map(|station| station.id).collect();
+    stations.extend(
+        fuzzy_matches
+            .into_iter()
+            .filter(|station| !found.contains(&station.id)),
+    );
+    stations.truncate(limit as usize);
+}
+
+/// Rebuilds the full-text index `stations_fts` from the names of all stations.
+pub async fn rebuild_search_index(conn: &mut SqliteConnection) -> Result<(), crate::Error> {
+    let stations = load_all(&mut *conn).await?;
+    let mut tx = conn.begin().await?;
+    sqlx::query!("DELETE FROM stations_fts")
+        .execute(&mut *tx)
+        .await?;
+    for station in &stations {
+        index(station, &mut *tx).await?;
+    }
+    // merges the segments the inserts created so that searching stays fast
+    sqlx::query!("INSERT INTO stations_fts (stations_fts) VALUES ('optimize')")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Adds the search keys of the station's names to the full-text index `stations_fts`, see
+/// [`search_key`].
+pub async fn index(
+    station: &Station,
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<(), crate::Error> {
+    // the words are stored separated by single spaces and the translations by line breaks so
+    // that searches can compare whole names with the searched words, see `search_by_name`
+    let name = words(&station.name).join(" ");
+    let translations = station
+        .translations()
+        .map(|translation| words(translation).join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    sqlx::query!(
+        "INSERT INTO stations_fts (rowid, name, translations) VALUES (?, ?, ?)",
+        station.id,
+        name,
+        translations
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn search_by_position(
     latitude: f64,
     longitude: f64,
     limit: i32,
     executor: impl sqlx::Executor<'_, Database = Sqlite>,
 ) -> Result<Vec<Station>, crate::Error> {
-    // First, get a larger set of candidates using a bounding box
-    // This is more efficient for the initial filtering
-    //
-    // TODO: extract into a constant that is visible
-    // and perhaps configurable as an env var
-    let approx_distance_deg = APPROXIMATE_DISTANCE;
-
-    let name_pattern = format!("%{}%", name.to_lowercase());
+    // First, get all candidates within a bounding box, which SQLite can filter efficiently, then
+    // rank them by their great-circle distance
+    let (latitude_delta, longitude_delta) = bounding_box(latitude);
+
     let stations = sqlx::query_as!(
         Station,
         r#"
         SELECT
             id,
             name,
+            slug,
             uic,
+            uic8_sncf,
             latitude,
             longitude,
+            parent_station_id,
             country,
+            time_zone,
+            is_city,
+            is_main_station,
+            is_airport,
+            is_suggestable,
+            country_hint,
+            main_station_hint,
+            same_as,
             info_de,
             info_en,
             info_es,
@@ -389,27 +801,220 @@ pub async fn search_by_name_and_position(
         FROM
             stations
         WHERE
-            lower(name) LIKE ?
-            AND latitude IS NOT NULL
+            latitude IS NOT NULL
             AND longitude IS NOT NULL
-            AND latitude BETWEEN $2 - $4 AND $2 + $4
-            AND longitude BETWEEN $3 - $4 AND $3 + $4
-        ORDER BY
-            ((latitude - $2) * (latitude - $2)) +
-            ((longitude - $3) * (longitude - $3))
-        ASC
-        LIMIT
-            $5
+            AND latitude BETWEEN $1 - $3 AND $1 + $3
+            AND longitude BETWEEN $2 - $4 AND $2 + $4
         "#,
-        name_pattern,
         latitude,
         longitude,
-        approx_distance_deg,
-        limit
+        latitude_delta,
+        longitude_delta
     )
     .fetch_all(executor)
     .await?;
 
-    // Return the closest 20
+    Ok(closest(stations, latitude, longitude, limit))
+}
+
+/// Searches stations near the position with names matching `name` like [`search_by_name`],
+/// closest first. Fuzzy matches are ranked below all other matches.
+///
+/// Unlike [`search_by_name`], this ranks only by distance, so the [`SearchConfig`] weights don't
+/// apply and the same name may be ranked differently once a position is passed.
+pub async fn search_by_name_and_position(
+    name: &str,
+    latitude: f64,
+    longitude: f64,
+    limit: i32,
+    fuzzy: bool,
+    db: impl sqlx::Acquire<'_, Database = Sqlite>,
+) -> Result<Vec<Station>, crate::Error> {
+    let Some(query) = full_text_query(name) else {
+        return Ok(Vec::new());
+    };
+    let mut conn = db.acquire().await?;
+    let mut stations =
+        search_by_full_text_query_and_position(&query, latitude, longitude, limit, &mut *conn)
+            .await?;
+    if fuzzy && stations.len() < limit as usize {
+        if let Some(query) = fuzzy_full_text_query(name, &mut conn).await? {
+            let fuzzy_limit = limit + stations.len() as i32;
+            let found = search_by_full_text_query_and_position(
+                &query,
+                latitude,
+                longitude,
+                fuzzy_limit,
+                &mut *conn,
+            )
+            .await?;
+            append_fuzzy_matches(&mut stations, found, limit);
+        }
+    }
     Ok(stations)
 }
+
+async fn search_by_full_text_query_and_position(
+    query: &str,
+    latitude: f64,
+    longitude: f64,
+    limit: i32,
+    executor: impl sqlx::Executor<'_, Database = Sqlite>,
+) -> Result<Vec<Station>, crate::Error> {
+    // First, get all candidates within a bounding box, which SQLite can filter efficiently, then
+    // rank them by their great-circle distance
+    let (latitude_delta, longitude_delta) = bounding_box(latitude);
+
+    let stations = sqlx::query_as!(
+        Station,
+        r#"
+        SELECT
+            stations.id,
+            stations.name,
+            stations.slug,
+            stations.uic,
+            stations.uic8_sncf,
+            stations.latitude,
+            stations.longitude,
+            stations.parent_station_id,
+            stations.country,
+            stations.time_zone,
+            stations.is_city,
+            stations.is_main_station,
+            stations.is_airport,
+            stations.is_suggestable,
+            stations.country_hint,
+            stations.main_station_hint,
+            stations.same_as,
+            stations.info_de,
+            stations.info_en,
+            stations.info_es,
+            stations.info_fr,
+            stations.info_it,
+            stations.info_nb,
+            stations.info_nl,
+            stations.info_cs,
+            stations.info_da,
+            stations.info_hu,
+            stations.info_ja,
+            stations.info_ko,
+            stations.info_pl,
+            stations.info_pt,
+            stations.info_ru,
+            stations.info_sv,
+            stations.info_tr,
+            stations.info_zh
+        FROM
+            stations_fts
+            JOIN stations ON stations.id = stations_fts.rowid
+        WHERE
+            stations_fts MATCH $1
+            AND stations.latitude IS NOT NULL
+            AND stations.longitude IS NOT NULL
+            AND stations.latitude BETWEEN $2 - $4 AND $2 + $4
+            AND stations.longitude BETWEEN $3 - $5 AND $3 + $5
+        "#,
+        query,
+        latitude,
+        longitude,
+        latitude_delta,
+        longitude_delta
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(closest(stations, latitude, longitude, limit))
+}
+
+/// The mean radius of the earth in kilometers.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Calculates the great-circle distance between two positions in kilometers using the haversine
+/// formula.
+pub fn great_circle_distance(
+    latitude_a: f64,
+    longitude_a: f64,
+    latitude_b: f64,
+    longitude_b: f64,
+) -> f64 {
+    let delta_latitude = (latitude_b - latitude_a).to_radians();
+    let delta_longitude = (longitude_b - longitude_a).to_radians();
+    let haversine = (delta_latitude / 2.0).sin().powi(2)
+        + latitude_a.to_radians().cos()
+            * latitude_b.to_radians().cos()
+            * (delta_longitude / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * haversine.sqrt().min(1.0).asin()
+}
+
+/// Returns how many degrees of latitude and longitude around a position at `latitude` to search
+/// for stations in.
+///
+/// Degrees of longitude get shorter towards the poles, so the box spans more of them there to
+/// cover about the same distance in each direction.
+fn bounding_box(latitude: f64) -> (f64, f64) {
+    let latitude_delta = APPROXIMATE_DISTANCE as f64;
+    let longitude_delta = (latitude_delta / latitude.to_radians().cos()).min(180.0);
+    (latitude_delta, longitude_delta)
+}
+
+/// Sorts the stations by their great-circle distance from the position, closest first, and keeps
+/// the `limit` closest ones.
+fn closest(stations: Vec<Station>, latitude: f64, longitude: f64, limit: i32) -> Vec<Station> {
+    let mut by_distance: Vec<(f64, Station)> = stations
+        .into_iter()
+        .map(|station| {
+            let distance = match (station.latitude, station.longitude) {
+                (Some(station_latitude), Some(station_longitude)) => {
+                    great_circle_distance(latitude, longitude, station_latitude, station_longitude)
+                }
+                _ => f64::INFINITY,
+            };
+            (distance, station)
+        })
+        .collect();
+    by_distance.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    by_distance
+        .into_iter()
+        .take(limit.max(0) as usize)
+        .map(|(_, station)| station)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_great_circle_distance() {
+        // Paris Gare du Nord to London St Pancras
+        let distance = great_circle_distance(48.8809, 2.3553, 51.5322, -0.1233);
+        assert!((distance - 344.0).abs() < 2.0, "{}", distance);
+        assert_eq!(great_circle_distance(60.0, 10.0, 60.0, 10.0), 0.0);
+
+        // at 60°N, a degree of longitude is only about half as long as a degree of latitude
+        let east = great_circle_distance(60.0, 10.0, 60.0, 10.72);
+        let north = great_circle_distance(60.0, 10.0, 60.54, 10.0);
+        assert!(east < north, "{} >= {}", east, north);
+    }
+
+    #[test]
+    fn test_first_character_range() {
+        assert_eq!(
+            first_character_range("frankfurt"),
+            (String::from("f"), String::from("g"))
+        );
+        assert_eq!(
+            first_character_range("zurich"),
+            (String::from("z"), String::from("{"))
+        );
+        assert_eq!(
+            first_character_range("łodz"),
+            (String::from("ł"), String::from("Ń"))
+        );
+        // surrogates aren't characters
+        assert_eq!(
+            first_character_range("\u{d7ff}"),
+            (String::from("\u{d7ff}"), String::from("\u{e000}"))
+        );
+    }
+}
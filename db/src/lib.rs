@@ -10,6 +10,9 @@ pub use sqlx::sqlite::SqlitePool as DbPool;
 /// Entity definitions and related functions
 pub mod entities;
 
+/// Normalization of station names for searching
+pub mod search;
+
 /// Starts a new database transaction.
 ///
 /// Example:
@@ -0,0 +1,42 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalizes text for matching names regardless of case and diacritics, e.g.
+/// "Lisboa Santa Apolónia" becomes "lisboa santa apolonia" and "Straße" becomes "strasse".
+///
+/// The text is case folded as defined by Unicode, stripped of the diacritics of Latin, Greek and
+/// Cyrillic letters and returned in NFC. Other combining marks change the letter rather than accent
+/// it, e.g. the dakuten turning ハ into バ, so they are kept. Station names are indexed and queries
+/// are matched by the keys this returns so both must always be normalized with it.
+pub fn search_key(text: &str) -> String {
+    caseless::default_case_fold_str(text)
+        .nfd()
+        .filter(|c| !is_diacritic(*c))
+        .nfc()
+        .collect()
+}
+
+/// Checks whether the character is in the Combining Diacritical Marks block, which holds the marks
+/// Latin, Greek and Cyrillic letters decompose into.
+fn is_diacritic(c: char) -> bool {
+    ('\u{0300}'..='\u{036f}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_key() {
+        assert_eq!(search_key("Lisboa Santa Apolónia"), "lisboa santa apolonia");
+        assert_eq!(search_key("ZÜRICH HB"), "zurich hb");
+        assert_eq!(search_key("Zu\u{0308}rich"), search_key("Zürich"));
+        assert_eq!(search_key("Straße"), "strasse");
+        assert_eq!(search_key("ΣΤΑΘΜΟΣ"), search_key("σταθμος"));
+        assert_eq!(search_key("Йошкар-Ола"), "иошкар-ола");
+        assert_eq!(search_key("東京駅"), "東京駅");
+        // dakuten and handakuten make distinct kana
+        assert_eq!(search_key("バス"), "バス");
+        assert_ne!(search_key("バ"), search_key("ハ"));
+        assert_ne!(search_key("パ"), search_key("ハ"));
+    }
+}
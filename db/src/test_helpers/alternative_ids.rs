@@ -0,0 +1,27 @@
+use crate::{
+    entities::alternative_ids::{AlternativeId, AlternativeIdChangeset},
+    DbPool,
+};
+use validator::Validate;
+
+pub async fn create(
+    alternative_id: AlternativeIdChangeset,
+    db: &DbPool,
+) -> Result<AlternativeId, anyhow::Error> {
+    alternative_id.validate()?;
+
+    sqlx::query!(
+        "INSERT INTO alternative_ids (station_id, scheme, value) VALUES (?, ?, ?)",
+        alternative_id.station_id,
+        alternative_id.scheme,
+        alternative_id.value
+    )
+    .execute(db)
+    .await?;
+
+    Ok(AlternativeId {
+        station_id: alternative_id.station_id,
+        scheme: alternative_id.scheme,
+        value: alternative_id.value,
+    })
+}
@@ -0,0 +1,37 @@
+use crate::{
+    entities::dataset_metadata::{DatasetMetadata, DatasetMetadataChangeset},
+    DbPool,
+};
+use validator::Validate;
+
+pub async fn create(
+    metadata: DatasetMetadataChangeset,
+    db: &DbPool,
+) -> Result<DatasetMetadata, anyhow::Error> {
+    metadata.validate()?;
+
+    sqlx::query!(
+        "INSERT INTO dataset_metadata (name, priority, source, etag, last_modified, sha256, row_count, synced_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        metadata.name,
+        metadata.priority,
+        metadata.source,
+        metadata.etag,
+        metadata.last_modified,
+        metadata.sha256,
+        metadata.row_count,
+        metadata.synced_at
+    )
+    .execute(db)
+    .await?;
+
+    Ok(DatasetMetadata {
+        name: metadata.name,
+        priority: metadata.priority,
+        source: metadata.source,
+        etag: metadata.etag,
+        last_modified: metadata.last_modified,
+        sha256: metadata.sha256,
+        row_count: metadata.row_count,
+        synced_at: metadata.synced_at,
+    })
+}
@@ -9,6 +9,8 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use tokio::fs;
 
+pub mod alternative_ids;
+pub mod dataset_metadata;
 pub mod stations;
 
 /// Sets up a dedicated database to be used in a test case.
@@ -1,5 +1,5 @@
 use crate::{
-    entities::stations::{Station, StationChangeset},
+    entities::stations::{self, Station, StationChangeset},
     DbPool,
 };
 use validator::Validate;
@@ -8,13 +8,24 @@ pub async fn create(station: StationChangeset, db: &DbPool) -> Result<Station, a
     station.validate()?;
 
     let record = sqlx::query!(
-        "INSERT INTO stations (id, name, uic, latitude, longitude, country, info_de, info_en, info_es, info_fr, info_it, info_nb, info_nl, info_cs, info_da, info_hu, info_ja, info_ko, info_pl, info_pt, info_ru, info_sv, info_tr, info_zh) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+        "INSERT INTO stations (id, name, slug, uic, uic8_sncf, latitude, longitude, parent_station_id, country, time_zone, is_city, is_main_station, is_airport, is_suggestable, country_hint, main_station_hint, same_as, info_de, info_en, info_es, info_fr, info_it, info_nb, info_nl, info_cs, info_da, info_hu, info_ja, info_ko, info_pl, info_pt, info_ru, info_sv, info_tr, info_zh) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
         station.id,
         station.name,
+        station.slug,
         station.uic,
+        station.uic8_sncf,
         station.latitude,
         station.longitude,
+        station.parent_station_id,
         station.country,
+        station.time_zone,
+        station.is_city,
+        station.is_main_station,
+        station.is_airport,
+        station.is_suggestable,
+        station.country_hint,
+        station.main_station_hint,
+        station.same_as,
         station.info_de,
         station.info_en,
         station.info_es,
@@ -37,13 +48,24 @@ pub async fn create(station: StationChangeset, db: &DbPool) -> Result<Station, a
     .fetch_one(db)
     .await?;
 
-    Ok(Station {
+    let station = Station {
         id: record.id,
         name: station.name,
+        slug: station.slug,
         uic: station.uic,
+        uic8_sncf: station.uic8_sncf,
         latitude: station.latitude,
         longitude: station.longitude,
+        parent_station_id: station.parent_station_id,
         country: station.country,
+        time_zone: station.time_zone,
+        is_city: station.is_city,
+        is_main_station: station.is_main_station,
+        is_airport: station.is_airport,
+        is_suggestable: station.is_suggestable,
+        country_hint: station.country_hint,
+        main_station_hint: station.main_station_hint,
+        same_as: station.same_as,
         info_de: station.info_de,
         info_en: station.info_en,
         info_es: station.info_es,
@@ -62,5 +84,37 @@ pub async fn create(station: StationChangeset, db: &DbPool) -> Result<Station, a
         info_sv: station.info_sv,
         info_tr: station.info_tr,
         info_zh: station.info_zh,
-    })
+    };
+    // stations are only found by name once they are indexed, which `cargo db sync` does for all
+    stations::index(&station, db).await?;
+
+    Ok(station)
+}
+
+/// Fills the search index with `count` random words that belong to no station so searches run
+/// against a vocabulary of a realistic size.
+pub async fn fill_search_vocabulary(count: i64, db: &DbPool) -> Result<(), anyhow::Error> {
+    sqlx::query(
+        r#"
+        WITH RECURSIVE
+            letter(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM letter WHERE i < 16),
+            word(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM word WHERE i < ?)
+        INSERT INTO stations_fts (rowid, name, translations)
+        SELECT
+            -word.i,
+            (
+                SELECT group_concat(char(97 + abs(random()) % 26), '')
+                FROM letter
+                WHERE letter.i <= 4 + word.i % 10
+            ),
+            ''
+        FROM
+            word
+        "#,
+    )
+    .bind(count)
+    .execute(db)
+    .await?;
+
+    Ok(())
 }
@@ -0,0 +1,13 @@
+use crate::{error::Error, state::SharedAppState};
+use axum::extract::State;
+use axum::http::StatusCode;
+
+// Endpoint handlers
+//
+/// Reopens the database so that a replaced database file takes effect.
+#[axum::debug_handler]
+pub async fn reload(State(app_state): State<SharedAppState>) -> Result<StatusCode, Error> {
+    app_state.reload_db_pool().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,84 @@
+use crate::osdm::OsdmProblem;
+use crate::{error::Error, state::SharedAppState};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use restations_db::entities::dataset_metadata;
+use serde::{Deserialize, Serialize};
+
+/// Describes the dataset the API serves so that clients can refer to it, e.g. when reporting
+/// data bugs.
+///
+/// The top-level fields describe the source with the highest priority for compatibility with
+/// clients that predate merging multiple sources; `sources` lists all of them.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetMeta {
+    #[serde(flatten)]
+    pub primary: SourceMeta,
+    pub sources: Vec<SourceMeta>,
+}
+
+/// Describes one of the sources the dataset was merged from.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMeta {
+    pub name: String,
+    pub priority: i64,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    pub sha256: String,
+    pub row_count: i64,
+    pub synced_at: String,
+}
+
+impl From<dataset_metadata::DatasetMetadata> for SourceMeta {
+    fn from(metadata: dataset_metadata::DatasetMetadata) -> Self {
+        SourceMeta {
+            name: metadata.name,
+            priority: metadata.priority,
+            source: metadata.source,
+            etag: metadata.etag,
+            last_modified: metadata.last_modified,
+            sha256: metadata.sha256,
+            row_count: metadata.row_count,
+            synced_at: metadata.synced_at,
+        }
+    }
+}
+
+pub enum MetaResponse {
+    Ok(DatasetMeta),
+    NotFound(OsdmProblem),
+}
+
+impl IntoResponse for MetaResponse {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+            Self::NotFound(body) => (StatusCode::NOT_FOUND, Json(body)).into_response(),
+        }
+    }
+}
+
+// Endpoint handlers
+//
+#[axum::debug_handler]
+pub async fn show(State(app_state): State<SharedAppState>) -> Result<MetaResponse, Error> {
+    match dataset_metadata::load_all(&app_state.db_pool()).await {
+        Ok(metadata) => Ok(MetaResponse::Ok(DatasetMeta {
+            // load_all never returns an empty list
+            primary: metadata[0].clone().into(),
+            sources: metadata.into_iter().map(SourceMeta::from).collect(),
+        })),
+        Err(restations_db::Error::NoRecordFound) => Ok(MetaResponse::NotFound(OsdmProblem {
+            code: String::from("not-found"),
+            title: "No dataset has been synchronized yet!".to_string(),
+            detail: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1 +1,3 @@
+pub mod admin;
+pub mod meta;
 pub mod places;
@@ -1,17 +1,27 @@
 use crate::osdm::{
     OsdmGeoPosition, OsdmPlace, OsdmPlaceRequest, OsdmPlaceResponse, PlacesResponse,
 };
+use crate::place_id::PlaceId;
 use crate::{error::Error, state::SharedAppState};
 use axum::extract::{Path, State};
 use axum::response::Json;
-use restations_db::entities::stations;
+use restations_db::entities::{alternative_ids, stations};
+use restations_db::DbPool;
+use std::collections::HashMap;
 use std::convert::From;
 
 // TODO perhaps make this configurable through an environment variable?
 pub const DEFAULT_NUMBER_OF_RESULTS: i32 = 20;
 
-impl From<stations::Station> for OsdmPlace {
-    fn from(station: stations::Station) -> Self {
+/// Converts a station with its alternative IDs and whether its UIC is shared with another station.
+impl From<(stations::Station, Vec<alternative_ids::AlternativeId>, bool)> for OsdmPlace {
+    fn from(
+        (station, alternative_ids, shared_uic): (
+            stations::Station,
+            Vec<alternative_ids::AlternativeId>,
+            bool,
+        ),
+    ) -> Self {
         let geo_position = match (station.latitude, station.longitude) {
             (Some(latitude), Some(longitude)) => Some(OsdmGeoPosition {
                 latitude,
@@ -20,44 +30,97 @@ impl From<stations::Station> for OsdmPlace {
             _ => None,
         };
 
+        // stations without a UIC, e.g. stops imported from GTFS feeds, are identified by their
+        // alternative ID or, if they have none, by their station ID; a UIC that is shared with
+        // another station, e.g. a city and its main station, does not identify the station
+        let id = if shared_uic {
+            PlaceId::Trainline(station.id)
+        } else if !station.uic.is_empty() {
+            PlaceId::Uic(station.uic)
+        } else if let Some(alternative_id) = alternative_ids.first() {
+            PlaceId::Alternative {
+                scheme: alternative_id.scheme.clone(),
+                value: alternative_id.value.clone(),
+            }
+        } else {
+            PlaceId::Trainline(station.id)
+        };
+
         OsdmPlace {
-            id: format!("urn:uic:stn:{}", station.uic),
+            id: id.to_string(),
             object_type: "StopPlace".into(),
             name: station.name,
-            alternative_ids: vec![],
+            alternative_ids: alternative_ids
+                .into_iter()
+                .map(|alternative_id| {
+                    PlaceId::Alternative {
+                        scheme: alternative_id.scheme,
+                        value: alternative_id.value,
+                    }
+                    .to_string()
+                })
+                .collect(),
             geo_position,
             _links: vec![],
         }
     }
 }
 
-impl From<Vec<stations::Station>> for OsdmPlaceResponse {
-    fn from(stations: Vec<stations::Station>) -> Self {
-        OsdmPlaceResponse {
-            places: stations.into_iter().map(|station| station.into()).collect(),
-        }
+/// Builds the response for a list of stations, loading each station's alternative IDs.
+async fn places_response(
+    stations: Vec<stations::Station>,
+    db_pool: &DbPool,
+) -> Result<OsdmPlaceResponse, Error> {
+    let station_ids: Vec<i64> = stations.iter().map(|station| station.id).collect();
+    let mut alternative_ids_by_station: HashMap<i64, Vec<alternative_ids::AlternativeId>> =
+        HashMap::new();
+    for alternative_id in alternative_ids::load_for_stations(&station_ids, db_pool).await? {
+        alternative_ids_by_station
+            .entry(alternative_id.station_id)
+            .or_default()
+            .push(alternative_id);
     }
+    let shared_uic_ids = stations::load_ids_with_shared_uic(&station_ids, db_pool).await?;
+
+    let places = stations
+        .into_iter()
+        .map(|station| {
+            let alternative_ids = alternative_ids_by_station
+                .remove(&station.id)
+                .unwrap_or_default();
+            let shared_uic = shared_uic_ids.contains(&station.id);
+            (station, alternative_ids, shared_uic).into()
+        })
+        .collect();
+
+    Ok(OsdmPlaceResponse { places })
 }
 
 // Endpoint handlers
 //
 #[axum::debug_handler]
 pub async fn list(State(app_state): State<SharedAppState>) -> Result<PlacesResponse, Error> {
-    let places = stations::load_all(&app_state.db_pool).await?;
+    let db_pool = app_state.db_pool();
+    let places = stations::load_all(&db_pool).await?;
 
-    Ok(PlacesResponse::Ok(places.into()))
+    Ok(PlacesResponse::Ok(places_response(places, &db_pool).await?))
 }
 
 pub async fn search(
     State(app_state): State<SharedAppState>,
     Json(place_req): Json<OsdmPlaceRequest>,
 ) -> Result<PlacesResponse, Error> {
+    let db_pool = app_state.db_pool();
     let maybe_place_input = place_req.place_input;
     let maybe_restrictions = place_req.restrictions;
 
     let limit = maybe_restrictions
         .and_then(|restrictions| restrictions.number_of_results)
         .unwrap_or(DEFAULT_NUMBER_OF_RESULTS);
+    let fuzzy = place_req
+        .extensions
+        .and_then(|extensions| extensions.fuzzy_search)
+        .unwrap_or(true);
 
     // TODO improve input handling
     let stations = match maybe_place_input {
@@ -70,13 +133,15 @@ pub async fn search(
                         position.latitude,
                         position.longitude,
                         limit,
-                        &app_state.db_pool,
+                        fuzzy,
+                        &db_pool,
                     )
                     .await?
                 }
                 // Search by name only
                 (Some(name), None) => {
-                    stations::search_by_name(&name, limit, &app_state.db_pool).await?
+                    stations::search_by_name(&name, limit, fuzzy, &app_state.search, &db_pool)
+                        .await?
                 }
                 // Search by position only
                 (None, Some(position)) => {
@@ -85,18 +150,20 @@ pub async fn search(
                         position.latitude,
                         position.longitude,
                         limit,
-                        &app_state.db_pool,
+                        &db_pool,
                     )
                     .await?
                 }
                 // No search criteria, return all
-                (None, None) => stations::load_all_within_limit(limit, &app_state.db_pool).await?,
+                (None, None) => stations::load_all_within_limit(limit, &db_pool).await?,
             }
         }
-        None => stations::load_all_within_limit(limit, &app_state.db_pool).await?,
+        None => stations::load_all_within_limit(limit, &db_pool).await?,
     };
 
-    Ok(PlacesResponse::Ok(stations.into()))
+    Ok(PlacesResponse::Ok(
+        places_response(stations, &db_pool).await?,
+    ))
 }
 
 #[axum::debug_handler]
@@ -104,7 +171,11 @@ pub async fn show(
     State(app_state): State<SharedAppState>,
     Path(place_id): Path<String>,
 ) -> Result<PlacesResponse, Error> {
-    let station = stations::load(place_id.parse().unwrap(), &app_state.db_pool).await?;
+    let place_id: PlaceId = place_id.parse()?;
+    let db_pool = app_state.db_pool();
+    let station = place_id.load(&db_pool).await?;
 
-    Ok(PlacesResponse::Ok(vec![station].into()))
+    Ok(PlacesResponse::Ok(
+        places_response(vec![station], &db_pool).await?,
+    ))
 }
@@ -1,4 +1,5 @@
 use crate::osdm::{OsdmProblem, PlacesResponse};
+use crate::place_id::InvalidPlaceId;
 use axum::{http::StatusCode, response::IntoResponse};
 use std::fmt::{Debug, Display};
 
@@ -10,6 +11,9 @@ pub enum Error {
     /// Errors that can occur as a result of a data layer operation.
     #[error("Database error")]
     Database(#[from] restations_db::Error),
+    /// A place ID passed in a request could not be parsed.
+    #[error("Invalid place ID")]
+    InvalidPlaceId(#[from] InvalidPlaceId),
     /// Any other error. Handled as an Internal Server Error.
     #[error("Error: {0}")]
     Other(#[from] anyhow::Error),
@@ -22,9 +26,18 @@ impl IntoResponse for Error {
                 let api_problem = OsdmProblem {
                     code: String::from("not-found"),
                     title: "Could not find place!".to_string(),
+                    detail: None,
                 };
                 PlacesResponse::NotFound(api_problem).into_response()
             }
+            Error::InvalidPlaceId(e) => {
+                let api_problem = OsdmProblem {
+                    code: String::from("invalid-place-id"),
+                    title: "Invalid place ID!".to_string(),
+                    detail: Some(e.to_string()),
+                };
+                PlacesResponse::BadRequest(api_problem).into_response()
+            }
             Error::Database(restations_db::Error::ValidationError(e)) => {
                 validation_error(e).into_response()
             }
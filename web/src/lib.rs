@@ -2,6 +2,8 @@
 use anyhow::Context;
 use axum::serve;
 use restations_config::{get_env, load_config, Config};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tracing::info;
 use tracing_panic::panic_hook;
@@ -15,6 +17,10 @@ pub mod error;
 pub mod middlewares;
 /// Contains OSDM-specific datatypes for responses and requests.
 pub mod osdm;
+/// Contains the parsing of the place IDs accepted by the API and their resolution to stations.
+pub mod place_id;
+/// Contains the reloading of the database, see [`state::AppState::reload_db_pool`].
+pub mod reload;
 /// Contains the application's route definitions.
 pub mod routes;
 /// Contains the application state definition and functionality to initialize it.
@@ -27,13 +33,24 @@ pub mod state;
 /// 1. Determine the environment the application is running in (see [`restations_config::get_env`])
 /// 2. Load the configuration (see [`restations_config::load_config`])
 /// 3. Initialize the application state (see [`state::init_app_state`])
-/// 4. Initialize the application's router (see [`routes::init_routes`])
-/// 5. Boot the application and start listening for requests on the configured interface and port
+/// 4. Start reloading the database on `SIGHUP` and, if configured, when its file changes
+///    (see [`reload`])
+/// 5. Initialize the application's router (see [`routes::init_routes`])
+/// 6. Boot the application and start listening for requests on the configured interface and port
 pub async fn run() -> anyhow::Result<()> {
     let env = get_env().context("Cannot get environment!")?;
     let config: Config = load_config(&env).context("Cannot load config!")?;
 
-    let app_state = state::init_app_state(config.clone()).await;
+    let app_state = Arc::new(state::init_app_state(config.clone()).await);
+
+    #[cfg(unix)]
+    reload::spawn_signal_handler(app_state.clone()).context("Cannot listen for SIGHUP!")?;
+    if config.reload.watch_interval > 0 {
+        reload::spawn_file_watcher(
+            app_state.clone(),
+            Duration::from_secs(config.reload.watch_interval),
+        );
+    }
 
     let app = routes::init_routes(app_state);
 
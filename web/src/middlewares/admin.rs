@@ -0,0 +1,43 @@
+use crate::osdm::OsdmProblem;
+use crate::state::SharedAppState;
+use axum::extract::{Request, State};
+use axum::http::{header::AUTHORIZATION, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Json, Response};
+
+/// Rejects requests that don't pass the configured admin token as a bearer token.
+///
+/// If no admin token is configured (see [`restations_config::ReloadConfig`]), all requests are
+/// rejected.
+pub async fn require_admin_token(
+    State(app_state): State<SharedAppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match (app_state.admin_token.as_deref(), token) {
+        (Some(expected), Some(token))
+            if !expected.is_empty() && constant_time_eq(expected.as_bytes(), token.as_bytes()) =>
+        {
+            next.run(request).await
+        }
+        _ => {
+            let api_problem = OsdmProblem {
+                code: String::from("unauthorized"),
+                title: "Missing or invalid admin token!".to_string(),
+                detail: None,
+            };
+            (StatusCode::UNAUTHORIZED, Json(api_problem)).into_response()
+        }
+    }
+}
+
+/// Compares the passed values in constant time so the token cannot be guessed via timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
@@ -1 +1 @@
-
+pub mod admin;
@@ -44,11 +44,23 @@ pub struct OsdmPlaceRestrictions {
     pub number_of_results: Option<i32>,
 }
 
+/// Options reStations supports beyond the OSDM specification. Other OSDM servers ignore them as
+/// an unknown field.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OsdmPlaceRequestExtensions {
+    /// Whether to also find places with names a few typos away from the searched name, defaults
+    /// to `true`
+    pub fuzzy_search: Option<bool>,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OsdmPlaceRequest {
     pub place_input: Option<OsdmInitialPlaceInput>,
     pub restrictions: Option<OsdmPlaceRestrictions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<OsdmPlaceRequestExtensions>,
 }
 
 //
@@ -64,10 +76,13 @@ pub struct OsdmPlaceResponse {
 pub struct OsdmProblem {
     pub code: String,
     pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
 }
 
 pub enum PlacesResponse {
     Ok(OsdmPlaceResponse),
+    BadRequest(OsdmProblem),
     NotFound(OsdmProblem),
 }
 
@@ -75,6 +90,7 @@ impl IntoResponse for PlacesResponse {
     fn into_response(self) -> Response {
         match self {
             Self::Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+            Self::BadRequest(body) => (StatusCode::BAD_REQUEST, Json(body)).into_response(),
             Self::NotFound(body) => (StatusCode::NOT_FOUND, Json(body)).into_response(),
         }
     }
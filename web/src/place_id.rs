@@ -0,0 +1,98 @@
+use restations_db::{entities::stations, DbPool};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const UIC_PREFIX: &str = "urn:uic:stn:";
+const ALTERNATIVE_PREFIX: &str = "urn:x_";
+const TRAINLINE_SCHEME: &str = "trainline";
+
+/// An identifier for a place as it can be passed to `GET /places/{id}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceId {
+    /// A UIC station code in the form the API returns it, e.g. `urn:uic:stn:8721428`.
+    Uic(String),
+    /// A Trainline station ID, e.g. `urn:x_trainline:8267`.
+    Trainline(i64),
+    /// A station code in a carrier's own scheme, e.g. `urn:x_sncf:FRPST`.
+    Alternative { scheme: String, value: String },
+    /// A bare number, e.g. `8721428`. This is looked up as a UIC first and as a Trainline ID if
+    /// no station with that UIC exists.
+    Numeric(String),
+}
+
+/// The error returned when a string cannot be parsed into a [`PlaceId`].
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("Invalid place ID: {0}")]
+pub struct InvalidPlaceId(pub String);
+
+impl FromStr for PlaceId {
+    type Err = InvalidPlaceId;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidPlaceId(id.to_string());
+
+        if let Some(uic) = id.strip_prefix(UIC_PREFIX) {
+            if is_numeric(uic) {
+                Ok(PlaceId::Uic(uic.to_string()))
+            } else {
+                Err(invalid())
+            }
+        } else if let Some(alternative_id) = id.strip_prefix(ALTERNATIVE_PREFIX) {
+            match alternative_id.split_once(':') {
+                Some((TRAINLINE_SCHEME, value)) => {
+                    value.parse().map(PlaceId::Trainline).map_err(|_| invalid())
+                }
+                Some((scheme, value)) if !scheme.is_empty() && !value.is_empty() => {
+                    Ok(PlaceId::Alternative {
+                        scheme: scheme.to_string(),
+                        value: value.to_string(),
+                    })
+                }
+                _ => Err(invalid()),
+            }
+        } else if is_numeric(id) {
+            Ok(PlaceId::Numeric(id.to_string()))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl Display for PlaceId {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PlaceId::Uic(uic) => write!(f, "{}{}", UIC_PREFIX, uic),
+            PlaceId::Trainline(id) => {
+                write!(f, "{}{}:{}", ALTERNATIVE_PREFIX, TRAINLINE_SCHEME, id)
+            }
+            PlaceId::Alternative { scheme, value } => {
+                write!(f, "{}{}:{}", ALTERNATIVE_PREFIX, scheme, value)
+            }
+            PlaceId::Numeric(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl PlaceId {
+    /// Loads the station this ID refers to.
+    pub async fn load(&self, db_pool: &DbPool) -> Result<stations::Station, restations_db::Error> {
+        match self {
+            PlaceId::Uic(uic) => stations::load_by_uic(uic, db_pool).await,
+            PlaceId::Trainline(id) => stations::load_by_id(*id, db_pool).await,
+            PlaceId::Alternative { scheme, value } => {
+                stations::load_by_alternative_id(scheme, value, db_pool).await
+            }
+            PlaceId::Numeric(id) => match stations::load_by_uic(id, db_pool).await {
+                Err(restations_db::Error::NoRecordFound) => match id.parse() {
+                    Ok(id) => stations::load_by_id(id, db_pool).await,
+                    Err(_) => Err(restations_db::Error::NoRecordFound),
+                },
+                result => result,
+            },
+        }
+    }
+}
+
+fn is_numeric(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+}
@@ -0,0 +1,59 @@
+use crate::state::SharedAppState;
+use std::time::{Duration, SystemTime};
+use tokio::fs::metadata;
+use tracing::{error, info, warn};
+
+/// Reloads the database whenever the server receives `SIGHUP`.
+#[cfg(unix)]
+pub fn spawn_signal_handler(app_state: SharedAppState) -> Result<(), anyhow::Error> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("Received SIGHUP, reloading database");
+            if let Err(e) = app_state.reload_db_pool().await {
+                error!(err.msg = %e, err.details = ?e, "Failed to reload database");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Reloads the database whenever its file changes, e.g. because `cargo db sync` replaced it.
+///
+/// The file is checked for changes to its modification time or size every `interval`.
+pub fn spawn_file_watcher(app_state: SharedAppState, interval: Duration) {
+    tokio::spawn(async move {
+        let db_file = app_state
+            .db_pool()
+            .connect_options()
+            .get_filename()
+            .to_path_buf();
+        let mut last_version = file_version(&db_file).await;
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            let version = file_version(&db_file).await;
+            if version.is_none() || version == last_version {
+                continue;
+            }
+            info!(db.file = %db_file.display(), "Database file changed, reloading database");
+            match app_state.reload_db_pool().await {
+                Ok(()) => last_version = version,
+                Err(e) => error!(err.msg = %e, err.details = ?e, "Failed to reload database"),
+            }
+        }
+    });
+}
+
+async fn file_version(file: &std::path::Path) -> Option<(SystemTime, u64)> {
+    match metadata(file).await {
+        Ok(metadata) => Some((metadata.modified().ok()?, metadata.len())),
+        Err(e) => {
+            warn!(db.file = %file.display(), err.msg = %e, "Cannot read database file metadata");
+            None
+        }
+    }
+}
@@ -1,20 +1,28 @@
-use crate::controllers::places;
-use crate::state::AppState;
+use crate::controllers::{admin, meta, places};
+use crate::middlewares::admin::require_admin_token;
+use crate::state::SharedAppState;
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
-use std::sync::Arc;
 
 /// Initializes the application's routes.
 ///
 /// This function maps paths (e.g. "/stations") and HTTP methods (e.g. "GET") to functions in [`crate::controllers`] as well as includes middlewares defined in [`crate::middlewares`] into the routing layer (see [`axum::Router`]).
-pub fn init_routes(app_state: AppState) -> Router {
-    let shared_app_state = Arc::new(app_state);
+pub fn init_routes(shared_app_state: SharedAppState) -> Router {
+    let admin_routes = Router::new()
+        .route("/admin/reload", post(admin::reload))
+        .route_layer(middleware::from_fn_with_state(
+            shared_app_state.clone(),
+            require_admin_token,
+        ));
 
     Router::new()
+        .route("/meta", get(meta::show))
         .route("/places", get(places::list))
         .route("/places", post(places::search))
         .route("/places/{id}", get(places::show))
+        .merge(admin_routes)
         .with_state(shared_app_state)
 }
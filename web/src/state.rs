@@ -1,10 +1,16 @@
-use restations_config::Config;
+use anyhow::Context;
+use restations_config::{Config, SearchConfig};
 use restations_db::{connect_pool, DbPool};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use tracing::info;
 
 /// The application's state that is available in [`crate::controllers`] and [`crate::middlewares`].
 pub struct AppState {
-    pub db_pool: DbPool,
+    db_pool: RwLock<DbPool>,
+    /// The bearer token required for the admin endpoints, see [`crate::middlewares::admin`]
+    pub admin_token: Option<String>,
+    /// The weights for ranking the stations found by name
+    pub search: SearchConfig,
 }
 
 /// The application's state as it is shared across the application, e.g. in controllers and middlewares.
@@ -12,6 +18,42 @@ pub struct AppState {
 /// This is the [`AppState`] struct wrappend in an [`std::sync::Arc`].
 pub type SharedAppState = Arc<AppState>;
 
+impl AppState {
+    pub fn new(db_pool: DbPool, admin_token: Option<String>, search: SearchConfig) -> Self {
+        AppState {
+            db_pool: RwLock::new(db_pool),
+            admin_token,
+            search,
+        }
+    }
+
+    /// Returns the current database connection pool.
+    ///
+    /// Request handlers should call this once and use the returned pool for the entire request so
+    /// that the request finishes on the same pool even if the pool is reloaded meanwhile.
+    pub fn db_pool(&self) -> DbPool {
+        self.db_pool.read().unwrap().clone()
+    }
+
+    /// Opens a new connection pool to the database and replaces the current one with it.
+    ///
+    /// Requests that are being handled keep using the previous pool, which is closed once the last
+    /// of them is done. This allows replacing the database file, e.g. via `cargo db sync`, without
+    /// restarting the server.
+    pub async fn reload_db_pool(&self) -> Result<(), anyhow::Error> {
+        let connect_options = self.db_pool().connect_options();
+        let db_pool = DbPool::connect_with((*connect_options).clone())
+            .await
+            .context("Failed to reopen database!")?;
+        *self.db_pool.write().unwrap() = db_pool;
+        info!(
+            db.file = %connect_options.get_filename().display(),
+            "Reloaded database"
+        );
+        Ok(())
+    }
+}
+
 /// Initializes the application state.
 ///
 /// This function creates an [`AppState`] based on the current [`restations_config::Config`].
@@ -20,5 +62,5 @@ pub async fn init_app_state(config: Config) -> AppState {
         .await
         .expect("Could not connect to database!");
 
-    AppState { db_pool }
+    AppState::new(db_pool, config.reload.admin_token, config.search)
 }
@@ -13,6 +13,7 @@ use restations_db::{
     DbPool,
 };
 use std::cell::OnceCell;
+use std::sync::Arc;
 use tower::ServiceExt;
 
 /// A request that a test sends to the application.
@@ -197,9 +198,11 @@ pub async fn setup() -> DbTestContext {
 
     let test_db_pool = setup_db(&config.database).await;
 
-    let app = init_routes(AppState {
-        db_pool: test_db_pool.clone(),
-    });
+    let app = init_routes(Arc::new(AppState::new(
+        test_db_pool.clone(),
+        config.reload.admin_token.clone(),
+        config.search.clone(),
+    )));
 
     DbTestContext {
         app,
@@ -0,0 +1,49 @@
+use axum::http::{header, Method};
+use googletest::prelude::{assert_that, eq};
+use restations_macros::db_test;
+use restations_web::osdm::OsdmProblem;
+use restations_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+
+// POST /admin/reload
+//
+#[db_test]
+async fn test_reload_ok(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/admin/reload")
+        .method(Method::POST)
+        .header(header::AUTHORIZATION, "Bearer test-admin-token")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(204));
+
+    let response = context.app.request("/places").send().await;
+    assert_that!(response.status(), eq(200));
+}
+
+#[db_test]
+async fn test_reload_missing_token(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/admin/reload")
+        .method(Method::POST)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(401));
+
+    let response_body: OsdmProblem = response.into_body().into_json().await;
+
+    assert_that!(response_body.code, eq("unauthorized"));
+}
+
+#[db_test]
+async fn test_reload_invalid_token(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/admin/reload")
+        .method(Method::POST)
+        .header(header::AUTHORIZATION, "Bearer wrong-token")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(401));
+}
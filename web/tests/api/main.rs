@@ -1 +1,3 @@
+mod admin_test;
+mod meta_test;
 mod places_test;
@@ -0,0 +1,73 @@
+use fake::{Fake, Faker};
+use googletest::prelude::{assert_that, eq, none, some};
+use restations_db::{entities::dataset_metadata, test_helpers};
+use restations_macros::db_test;
+use restations_web::controllers::meta::DatasetMeta;
+use restations_web::osdm::OsdmProblem;
+use restations_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+
+// GET /meta
+//
+#[db_test]
+async fn test_show_not_synchronized(context: &DbTestContext) {
+    let response = context.app.request("/meta").send().await;
+    assert_that!(response.status(), eq(404));
+
+    let response_body: OsdmProblem = response.into_body().into_json().await;
+
+    assert_that!(response_body.code, eq("not-found"));
+}
+
+#[db_test]
+async fn test_show_ok(context: &DbTestContext) {
+    let mut changeset: dataset_metadata::DatasetMetadataChangeset = Faker.fake();
+    changeset.etag = Some(String::from("\"abc123\""));
+    changeset.last_modified = None;
+    test_helpers::dataset_metadata::create(changeset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context.app.request("/meta").send().await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: DatasetMeta = response.into_body().into_json().await;
+
+    assert_that!(response_body.primary.name, eq(&changeset.name));
+    assert_that!(response_body.primary.source, eq(&changeset.source));
+    assert_that!(response_body.primary.etag, some(eq("\"abc123\"")));
+    assert_that!(response_body.primary.last_modified, none());
+    assert_that!(response_body.primary.sha256, eq(&changeset.sha256));
+    assert_that!(response_body.primary.row_count, eq(changeset.row_count));
+    assert_that!(response_body.primary.synced_at, eq(&changeset.synced_at));
+    assert_that!(response_body.sources.len(), eq(1));
+}
+
+#[db_test]
+async fn test_show_multiple_sources(context: &DbTestContext) {
+    let mut gtfs: dataset_metadata::DatasetMetadataChangeset = Faker.fake();
+    gtfs.name = String::from("gtfs");
+    gtfs.priority = 10;
+    test_helpers::dataset_metadata::create(gtfs, &context.db_pool)
+        .await
+        .unwrap();
+    let mut trainline: dataset_metadata::DatasetMetadataChangeset = Faker.fake();
+    trainline.name = String::from("trainline");
+    trainline.priority = 100;
+    test_helpers::dataset_metadata::create(trainline.clone(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context.app.request("/meta").send().await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: DatasetMeta = response.into_body().into_json().await;
+
+    assert_that!(response_body.primary.name, eq("trainline"));
+    assert_that!(response_body.primary.source, eq(&trainline.source));
+    let names: Vec<&str> = response_body
+        .sources
+        .iter()
+        .map(|source| source.name.as_str())
+        .collect();
+    assert_that!(names, eq(&vec!["trainline", "gtfs"]));
+}
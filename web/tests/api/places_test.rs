@@ -4,10 +4,17 @@ use axum::{
 };
 use fake::{Fake, Faker};
 use googletest::prelude::{assert_that, eq};
-use restations_db::{entities::stations, test_helpers::stations::create};
+use restations_db::{
+    entities::{alternative_ids, stations},
+    test_helpers::{
+        self,
+        stations::{create, fill_search_vocabulary},
+    },
+};
 use restations_macros::db_test;
 use restations_web::osdm::{
-    OsdmInitialPlaceInput, OsdmPlaceRequest, OsdmPlaceResponse, OsdmPlaceRestrictions, OsdmProblem,
+    OsdmGeoPosition, OsdmInitialPlaceInput, OsdmPlaceRequest, OsdmPlaceResponse,
+    OsdmPlaceRestrictions, OsdmProblem,
 };
 use restations_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
 use serde_json::json;
@@ -55,6 +62,7 @@ async fn test_search_by_name_ok(context: &DbTestContext) {
             name: Some(String::from("Berlin")),
             geo_position: None,
         }),
+        extensions: None,
     });
     let response = context
         .app
@@ -95,6 +103,7 @@ async fn test_search_by_name_with_results_limit_ok(context: &DbTestContext) {
             name: Some(String::from("Berlin")),
             geo_position: None,
         }),
+        extensions: None,
     });
     let response = context
         .app
@@ -113,6 +122,265 @@ async fn test_search_by_name_with_results_limit_ok(context: &DbTestContext) {
     assert_that!(&response_body.places[0].name, eq("Berlin-Lichtenberg"));
 }
 
+#[db_test]
+async fn test_search_by_name_ranking(context: &DbTestContext) {
+    for (name, info_fr, is_main_station, is_city) in [
+        ("Gare de Paris Bercy", None, false, false),
+        ("Parigi Centrale", Some("Paris Centrale"), false, false),
+        ("Paris Gare de Lyon", None, true, false),
+        ("Paris", None, false, true),
+    ] {
+        let mut changeset: stations::StationChangeset = Faker.fake();
+        changeset.name = String::from(name);
+        changeset.info_fr = info_fr.map(String::from);
+        changeset.is_main_station = is_main_station;
+        changeset.is_city = is_city;
+        changeset.is_suggestable = false;
+        create(changeset.clone(), &context.db_pool).await.unwrap();
+    }
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("Paris")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    let names: Vec<&str> = response_body
+        .places
+        .iter()
+        .map(|place| place.name.as_str())
+        .collect();
+    assert_that!(
+        names,
+        eq(&vec![
+            "Paris",
+            "Paris Gare de Lyon",
+            "Parigi Centrale",
+            "Gare de Paris Bercy"
+        ])
+    );
+}
+
+#[db_test]
+async fn test_search_by_name_prefix_ignoring_diacritics(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Überlingen");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Bremen");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("uberl")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(1));
+    assert_that!(&response_body.places[0].name, eq("Überlingen"));
+}
+
+#[db_test]
+async fn test_search_by_name_without_uic(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.id = 9162;
+    changeset.name = String::from("Überlingen Therme");
+    changeset.uic = String::new();
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("therme")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(1));
+    assert_that!(&response_body.places[0].name, eq("Überlingen Therme"));
+    assert_that!(&response_body.places[0].id, eq("urn:x_trainline:9162"));
+}
+
+#[db_test]
+async fn test_search_by_name_fuzzy(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Bâle");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Basel SBB");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Bern");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("basle")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    let mut names: Vec<&str> = response_body
+        .places
+        .iter()
+        .map(|place| place.name.as_str())
+        .collect();
+    names.sort();
+    assert_that!(names, eq(&vec!["Basel SBB", "Bâle"]));
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("base")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    // exact and prefix matches come before fuzzy ones
+    assert_that!(response_body.places.len(), eq(2));
+    assert_that!(&response_body.places[0].name, eq("Basel SBB"));
+    assert_that!(&response_body.places[1].name, eq("Bâle"));
+}
+
+#[db_test]
+async fn test_search_by_name_fuzzy_large_vocabulary(context: &DbTestContext) {
+    // about as many words as the names of all stations in Europe consist of
+    fill_search_vocabulary(100_000, &context.db_pool)
+        .await
+        .unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Frankfurt (Main) Hbf");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    // a typo in the first character isn't tolerated
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Brankfurt");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("Frankfrut")),
+            geo_position: None,
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(1));
+    assert_that!(&response_body.places[0].name, eq("Frankfurt (Main) Hbf"));
+}
+
+#[db_test]
+async fn test_search_by_name_fuzzy_disabled(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Frankfurt (Main) Hbf");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = r#"
+        {
+            "placeInput": {
+                "name": "Frankfrut"
+            },
+            "extensions": {
+                "fuzzySearch": false
+            }
+        }
+    "#;
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(0));
+}
+
 #[db_test]
 async fn test_search_other_languages(context: &DbTestContext) {
     let mut changeset: stations::StationChangeset = Faker.fake();
@@ -129,7 +397,8 @@ async fn test_search_other_languages(context: &DbTestContext) {
         place_input: Some(OsdmInitialPlaceInput {
             name: Some(String::from("Seville")),
             geo_position: None,
-        })
+        }),
+        extensions: None,
     });
     let response = context
         .app
@@ -199,6 +468,49 @@ async fn test_search_geo_position(context: &DbTestContext) {
     assert_that!(second.name, eq("London Waterloo"));
 }
 
+#[db_test]
+async fn test_search_geo_position_great_circle_distance(context: &DbTestContext) {
+    // at 60°N, 0.54° of latitude are about 60 km but 0.72° of longitude only about 40 km
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("60 km north");
+    changeset.latitude = Some(60.54);
+    changeset.longitude = Some(10.0);
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("40 km east");
+    changeset.latitude = Some(60.0);
+    changeset.longitude = Some(10.72);
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: None,
+            geo_position: Some(OsdmGeoPosition {
+                latitude: 60.0,
+                longitude: 10.0,
+            }),
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(2));
+    assert_that!(&response_body.places[0].name, eq("40 km east"));
+    assert_that!(&response_body.places[1].name, eq("60 km north"));
+}
+
 #[db_test]
 async fn test_search_geo_position_with_results_limit(context: &DbTestContext) {
     let mut changeset: stations::StationChangeset = Faker.fake();
@@ -251,6 +563,49 @@ async fn test_search_geo_position_with_results_limit(context: &DbTestContext) {
 // Weird requests that we still gracefully handle
 //
 
+#[db_test]
+async fn test_search_by_name_and_geo_position_ignoring_case_and_diacritics(
+    context: &DbTestContext,
+) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("ZÜRICH HB");
+    changeset.latitude = Some(47.378);
+    changeset.longitude = Some(8.540);
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Zug");
+    changeset.latitude = Some(47.173);
+    changeset.longitude = Some(8.515);
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let payload = json!(OsdmPlaceRequest {
+        restrictions: None,
+        place_input: Some(OsdmInitialPlaceInput {
+            name: Some(String::from("zürich")),
+            geo_position: Some(OsdmGeoPosition {
+                latitude: 47.37,
+                longitude: 8.54,
+            }),
+        }),
+        extensions: None,
+    });
+    let response = context
+        .app
+        .request("/places")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(1));
+    assert_that!(&response_body.places[0].name, eq("ZÜRICH HB"));
+}
+
 #[db_test]
 async fn test_search_unknown_parameters(context: &DbTestContext) {
     // Note: we're posting json here to assert camelcasing of request structs
@@ -317,6 +672,181 @@ async fn test_show_ok(context: &DbTestContext) {
     assert_that!(place.name, eq("Test Station"));
 }
 
+#[db_test]
+async fn test_show_by_uic_urn(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Lisboa Santa Apolónia");
+    changeset.uic = String::from("8721428");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let response = context
+        .app
+        .request("/places/urn:uic:stn:8721428")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places[0].name, eq("Lisboa Santa Apolónia"));
+}
+
+#[db_test]
+async fn test_show_by_shared_uic(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.id = 4916;
+    changeset.name = String::from("Paris");
+    changeset.uic = String::from("8768600");
+    changeset.is_city = true;
+    changeset.is_main_station = false;
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.id = 4924;
+    changeset.name = String::from("Paris Gare de Lyon");
+    changeset.uic = String::from("8768600");
+    changeset.is_city = false;
+    changeset.is_main_station = true;
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let response = context
+        .app
+        .request("/places/urn:uic:stn:8768600")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places.len(), eq(1));
+    let place = &response_body.places[0];
+    assert_that!(place.name, eq("Paris Gare de Lyon"));
+    assert_that!(place.id, eq("urn:x_trainline:4924"));
+
+    let response = context
+        .app
+        .request("/places/urn:x_trainline:4916")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places[0].name, eq("Paris"));
+    assert_that!(response_body.places[0].id, eq("urn:x_trainline:4916"));
+}
+
+#[db_test]
+async fn test_show_by_trainline_id(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.id = 8267;
+    changeset.name = String::from("Lisboa Santa Apolónia");
+    create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let response = context
+        .app
+        .request("/places/urn:x_trainline:8267")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places[0].name, eq("Lisboa Santa Apolónia"));
+
+    let response = context.app.request("/places/8267").send().await;
+    assert_that!(response.status(), eq(200));
+}
+
+#[db_test]
+async fn test_show_by_carrier_id(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.name = String::from("Paris Gare de l’Est");
+    let station = create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: alternative_ids::AlternativeIdChangeset = Faker.fake();
+    changeset.station_id = station.id;
+    changeset.scheme = String::from("sncf");
+    changeset.value = String::from("FRPST");
+    test_helpers::alternative_ids::create(changeset, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context.app.request("/places/urn:x_sncf:FRPST").send().await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places[0].name, eq("Paris Gare de l’Est"));
+}
+
+#[db_test]
+async fn test_show_station_without_uic(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.uic = String::new();
+    changeset.name = String::from("Market Square");
+    let station = create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    let mut changeset: alternative_ids::AlternativeIdChangeset = Faker.fake();
+    changeset.station_id = station.id;
+    changeset.scheme = String::from("gtfs_regional");
+    changeset.value = String::from("S2");
+    test_helpers::alternative_ids::create(changeset, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request("/places/urn:x_gtfs_regional:S2")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(response_body.places[0].id, eq("urn:x_gtfs_regional:S2"));
+    assert_that!(response_body.places[0].name, eq("Market Square"));
+}
+
+#[db_test]
+async fn test_show_invalid_id(context: &DbTestContext) {
+    let response = context.app.request("/places/urn:uic:stn:abc").send().await;
+    assert_that!(response.status(), eq(400));
+
+    let problem: OsdmProblem = response.into_body().into_json::<OsdmProblem>().await;
+
+    assert_that!(problem.code, eq("invalid-place-id"));
+}
+
+#[db_test]
+async fn test_show_alternative_ids(context: &DbTestContext) {
+    let mut changeset: stations::StationChangeset = Faker.fake();
+    changeset.uic = String::from("8711300");
+    let station = create(changeset.clone(), &context.db_pool).await.unwrap();
+
+    for (scheme, value) in [("sncf", "FRPST"), ("db", "8700011")] {
+        let mut changeset: alternative_ids::AlternativeIdChangeset = Faker.fake();
+        changeset.station_id = station.id;
+        changeset.scheme = String::from(scheme);
+        changeset.value = String::from(value);
+        test_helpers::alternative_ids::create(changeset, &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    let response = context.app.request("/places/8711300").send().await;
+    assert_that!(response.status(), eq(200));
+
+    let response_body: OsdmPlaceResponse = response.into_body().into_json().await;
+
+    assert_that!(
+        response_body.places[0].alternative_ids,
+        eq(&vec![
+            String::from("urn:x_db:8700011"),
+            String::from("urn:x_sncf:FRPST")
+        ])
+    );
+}
+
 #[db_test]
 async fn test_show_not_found(context: &DbTestContext) {
     let response = context.app.request("/places/1").send().await;